@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::Utc;
+use log::{error, info};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+pub use crate::proto::api::audit::{Action, Entry};
+use crate::repository::Repository;
+
+/// Name of the audit log in the data directory of the repository.
+const FILE: &str = "audit.log";
+
+/// Trail of the actions performed by the users.
+///
+/// Each action is appended as a single line of JSON to the audit log in the data directory of the repository.
+pub struct Audit {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl Audit {
+    pub async fn open(repository: &Repository) -> Result<Self> {
+        let path = repository.data_path();
+        tokio::fs::create_dir_all(&path).await?;
+
+        return Ok(Self {
+            path: path.join(FILE),
+            lock: Mutex::new(()),
+        });
+    }
+
+    /// Records an action performed by the given user.
+    pub async fn record(&self, user: &str, action: Action) -> Result<()> {
+        let entry = Entry {
+            time: Utc::now(),
+            user: user.to_string(),
+            action,
+        };
+
+        info!("Audit: {} {:?}", entry.user, entry.action);
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        let _lock = self.lock.lock().await;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path).await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        return Ok(());
+    }
+
+    /// Records an action which has already been performed by the given user.
+    ///
+    /// The action can not be undone anymore, so a failure to record it is logged instead of failing the request.
+    pub async fn report(&self, user: &str, action: Action) {
+        if let Err(err) = self.record(user, action.clone()).await {
+            error!("Failed to record {:?} by {} in audit log: {:#}", action, user, err);
+        }
+    }
+
+    /// Returns all recorded actions, oldest first.
+    pub async fn entries(&self) -> Result<Vec<Entry>> {
+        let _lock = self.lock.lock().await;
+
+        let data = match tokio::fs::read(&self.path).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        return data.split(|&c| c == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| Ok(serde_json::from_slice(line)?))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::proto::model::DocId;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let audit = Audit::open(&repository).await.unwrap();
+        assert!(audit.entries().await.unwrap().is_empty());
+
+        let doc = DocId::random();
+        audit.record("alice", Action::Upload { doc }).await.unwrap();
+        audit.record("bob", Action::Archive { doc }).await.unwrap();
+
+        // The entries are persisted in the repository
+        let audit = Audit::open(&repository).await.unwrap();
+        let entries = audit.entries().await.unwrap();
+
+        assert_eq!(entries.iter().map(|entry| (entry.user.as_str(), entry.action.clone())).collect::<Vec<_>>(), vec![
+            ("alice", Action::Upload { doc }),
+            ("bob", Action::Archive { doc }),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_report_failed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let audit = Audit::open(&repository).await.unwrap();
+
+        // Make the audit log unwritable
+        tokio::fs::create_dir_all(repository.data_path().join(FILE)).await.unwrap();
+
+        let doc = DocId::random();
+        assert!(audit.record("alice", Action::Upload { doc }).await.is_err());
+
+        // Reporting an action does not fail
+        audit.report("alice", Action::Upload { doc }).await;
+    }
+}
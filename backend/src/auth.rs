@@ -1,95 +1,328 @@
-use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use jsonwebtoken::{DecodingKey, EncodingKey};
+use log::info;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::config::Auth;
+use crate::config::{Auth, Session as SessionConfig, User};
+use crate::keys::Keys;
+use crate::proto::api::auth::AuthResponse;
+use crate::proto::model::Scope;
+use crate::revocations::Revocations;
+
+/// Seconds since the epoch.
+fn now() -> u64 {
+    return SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("System time before epoch")
+        .as_secs();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Use {
+    /// Authenticates requests
+    Access,
+
+    /// Issues new tokens for the session
+    Refresh,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
+    /// ID of the session shared by all tokens issued for the session
+    pub jti: String,
+
     pub exp: u64,
+
+    /// Start of the session as seconds since the epoch
+    pub auth_time: u64,
+
+    #[serde(rename = "use")]
+    pub usage: Use,
+
+    pub username: String,
+    pub scopes: HashSet<Scope>,
+
+    /// ID of a refresh token which can be used only once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rid: Option<String>,
 }
 
-impl Claims {
-    pub fn new(timeout: Duration) -> Self {
-        return Self {
-            exp: (SystemTime::now() + timeout)
-                .duration_since(std::time::UNIX_EPOCH)
-                .expect("System time before epoch")
-                .as_secs(),
-        };
-    }
+/// A session started by logging in.
+#[derive(Debug)]
+struct Session {
+    id: String,
+    started: u64,
 }
 
 #[derive(Debug)]
 pub struct Token {
-    private: (),
+    username: String,
+    scopes: HashSet<Scope>,
+
+    /// The session of a logged in user - missing for API keys
+    session: Option<Session>,
 }
 
-pub struct Authenticator {
+impl Token {
+    /// The name of the logged in user or of the API key.
+    pub fn username(&self) -> &str {
+        return &self.username;
+    }
+
+    /// The scopes granted to the session or API key.
+    pub fn scopes(&self) -> &HashSet<Scope> {
+        return &self.scopes;
+    }
+
+    pub fn has(&self, scope: Scope) -> bool {
+        return self.scopes.contains(&scope);
+    }
+}
+
+/// Name of the account using the single configured password hash if no username is given.
+const DEFAULT_USERNAME: &str = "admin";
+
+struct Account {
     passhash: String,
 
+    /// The scopes a session of the user can be granted
+    scopes: HashSet<Scope>,
+}
+
+impl From<User> for Account {
+    fn from(user: User) -> Self {
+        return match user {
+            User::Passhash(passhash) => Self { passhash, scopes: Scope::all() },
+            User::Restricted { passhash, scopes } => Self { passhash, scopes },
+        };
+    }
+}
+
+pub struct Authenticator {
+    users: HashMap<String, Account>,
+
     jwt_decoding_key: DecodingKey<'static>,
     jwt_encoding_key: EncodingKey,
 
-    jwt_token_duration: Duration,
+    lifetimes: SessionConfig,
+    revocations: Revocations,
 
+    /// Static API keys from the config
     api_keys: HashMap<String, String>,
+
+    /// API keys managed at runtime
+    keys: Arc<Keys>,
 }
 
 impl Authenticator {
-    pub async fn from_config(config: Auth) -> Result<Self> {
+    pub async fn from_config(config: Auth, keys: Arc<Keys>, revocations: Revocations) -> Result<Self> {
         // TODO: Add some sanity checks (empty values, ...)
 
+        if config.session.access == 0 || config.session.refresh == 0 || config.session.max == 0 {
+            return Err(anyhow!("Session lifetimes must not be zero"));
+        }
+
+        let mut entries = Vec::new();
+
+        if let Some(path) = config.users_file {
+            let data = tokio::fs::read(&path).await
+                .map_err(|err| anyhow!("Can not read users file {}: {}", path, err))?;
+            entries.extend(serde_yaml::from_slice::<HashMap<String, User>>(&data)?);
+        }
+
+        entries.extend(config.users);
+
+        if let Some(passhash) = config.passhash {
+            entries.push((config.username.unwrap_or_else(|| DEFAULT_USERNAME.to_string()), User::Passhash(passhash)));
+        }
+
+        // An account defined in multiple places must not silently replace another one
+        let mut users = HashMap::new();
+        for (username, user) in entries {
+            if users.contains_key(&username) {
+                return Err(anyhow!("User {} is defined more than once", username));
+            }
+
+            users.insert(username, Account::from(user));
+        }
+
+        info!("Loaded {} user accounts", users.len());
+
         Ok(Self {
-            passhash: config.passhash,
+            users,
 
             jwt_decoding_key: DecodingKey::from_secret(config.secret.as_bytes()).into_static(),
             jwt_encoding_key: EncodingKey::from_secret(config.secret.as_bytes()),
 
-            jwt_token_duration: Duration::from_secs(60 * 60), // TODO: Make configurable
+            lifetimes: config.session,
+            revocations,
 
             api_keys: config.api_keys,
+            keys,
         })
     }
 
-    pub async fn verify_token(&self, bearer: &str) -> Result<Token> {
-        jsonwebtoken::decode::<Claims>(
+    async fn decode(&self, bearer: &str, usage: Use) -> Result<Claims> {
+        let claims = jsonwebtoken::decode::<Claims>(
             bearer,
             &self.jwt_decoding_key,
             &jsonwebtoken::Validation::default(),
-        )?;
+        )?.claims;
 
-        Ok(Token { private: () })
+        if claims.usage != usage {
+            return Err(anyhow!("Token can not be used for {:?}", usage));
+        }
+
+        if self.revocations.is_revoked(&claims.jti).await {
+            return Err(anyhow!("Session has been revoked"));
+        }
+
+        Ok(claims)
     }
 
-    pub async fn sign_token(&self, _: &Token) -> Result<String> {
-        let bearer = jsonwebtoken::encode(
+    pub async fn verify_token(&self, bearer: &str) -> Result<Token> {
+        let claims = self.decode(bearer, Use::Access).await?;
+
+        Ok(Token {
+            username: claims.username,
+            scopes: claims.scopes,
+            session: Some(Session { id: claims.jti, started: claims.auth_time }),
+        })
+    }
+
+    /// Issues a new access and refresh token for the session.
+    ///
+    /// Both tokens expire at the latest when the session reaches its maximum lifetime.
+    pub async fn issue(&self, token: &Token) -> Result<AuthResponse> {
+        let session = token.session.as_ref()
+            .ok_or_else(|| anyhow!("Token does not belong to a session"))?;
+
+        let now = now();
+        let end = session.started + self.lifetimes.max;
+        if now >= end {
+            return Err(anyhow!("Session has expired"));
+        }
+
+        let sign = |usage: Use, lifetime: u64| jsonwebtoken::encode(
             &jsonwebtoken::Header::default(),
-            &Claims::new(self.jwt_token_duration),
+            &Claims {
+                jti: session.id.clone(),
+                exp: std::cmp::min(now + lifetime, end),
+                auth_time: session.started,
+                usage,
+                username: token.username.clone(),
+                scopes: token.scopes.clone(),
+                rid: (usage == Use::Refresh).then(|| Uuid::new_v4().to_string()),
+            },
             &self.jwt_encoding_key,
-        )?;
+        );
 
-        Ok(bearer)
+        Ok(AuthResponse {
+            access: sign(Use::Access, self.lifetimes.access)?,
+            refresh: sign(Use::Refresh, self.lifetimes.refresh)?,
+        })
     }
 
-    pub async fn login(&self, password: &str) -> Option<Token> {
-        // TODO: Verify passhash is valid on config load
+    /// Verifies a refresh token and returns the session to issue new tokens for.
+    ///
+    /// Each refresh token can be used only once. Using it again revokes the whole session as the token may have been
+    /// stolen. The session is granted only the scopes the user still has.
+    pub async fn refresh(&self, refresh: &str) -> Result<Token> {
+        let claims = self.decode(refresh, Use::Refresh).await?;
 
-        if bcrypt::verify(password.as_bytes(), &self.passhash).ok()? {
-            return Some(Token { private: () });
-        } else {
-            return None;
+        let rid = claims.rid.as_ref()
+            .ok_or_else(|| anyhow!("Refresh token without ID"))?;
+
+        let now = now();
+        if !self.revocations.revoke(rid, claims.exp, now).await? {
+            self.revocations.revoke(&claims.jti, claims.auth_time + self.lifetimes.max, now).await?;
+            return Err(anyhow!("Refresh token has already been used"));
         }
+
+        let account = self.users.get(&claims.username)
+            .ok_or_else(|| anyhow!("User {} does not exist anymore", claims.username))?;
+
+        let scopes = claims.scopes.intersection(&account.scopes).copied().collect::<HashSet<_>>();
+        if scopes.is_empty() {
+            return Err(anyhow!("User {} has none of the session scopes anymore", claims.username));
+        }
+
+        Ok(Token {
+            username: claims.username,
+            scopes,
+            session: Some(Session { id: claims.jti, started: claims.auth_time }),
+        })
     }
 
-    pub async fn verify_key(&self, username: &str, password: &str) -> Option<Token> {
-        if bcrypt::verify(password, self.api_keys.get(username)?).ok()? {
-            return Some(Token { private: () });
-        } else {
+    /// Revokes all tokens of the session.
+    pub async fn logout(&self, token: &Token) -> Result<()> {
+        let session = token.session.as_ref()
+            .ok_or_else(|| anyhow!("Token does not belong to a session"))?;
+
+        info!("Logout: {}", token.username);
+
+        self.revocations.revoke(&session.id, session.started + self.lifetimes.max, now()).await?;
+
+        return Ok(());
+    }
+
+    /// Logs in a user by password.
+    ///
+    /// The username can be omitted if there is only a single account. The session is granted all scopes of the user
+    /// unless restricted to some of them.
+    pub async fn login(&self, username: Option<&str>, password: &str, scopes: Option<&HashSet<Scope>>) -> Option<Token> {
+        // TODO: Verify passhashes are valid on config load
+
+        let (username, account) = match username {
+            Some(username) => self.users.get_key_value(username)?,
+            None if self.users.len() == 1 => self.users.iter().next()?,
+            None => return None,
+        };
+
+        if !bcrypt::verify(password.as_bytes(), &account.passhash).ok()? {
+            return None;
+        }
+
+        let scopes = match scopes {
+            Some(scopes) => account.scopes.intersection(scopes).copied().collect(),
+            None => account.scopes.clone(),
+        };
+
+        if scopes.is_empty() {
             return None;
         }
+
+        return Some(Token {
+            username: username.clone(),
+            scopes,
+            session: Some(Session {
+                id: Uuid::new_v4().to_string(),
+                started: now(),
+            }),
+        });
+    }
+
+    pub async fn verify_key(&self, username: &str, password: &str) -> Option<Token> {
+        let scopes = match self.api_keys.get(username) {
+            Some(passhash) => bcrypt::verify(password, passhash).ok()?.then(Scope::all)?,
+            None => self.keys.verify(username, password).await?,
+        };
+
+        return Some(Token { username: username.to_string(), scopes, session: None });
+    }
+
+    /// Whether a static API key with the given name is configured.
+    pub fn is_configured_key(&self, username: &str) -> bool {
+        return self.api_keys.contains_key(username);
+    }
+
+    pub fn keys(&self) -> &Keys {
+        return &self.keys;
     }
 }
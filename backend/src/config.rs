@@ -1,17 +1,73 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncReadExt;
 
+use crate::proto::model::Scope;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum User {
+    /// The password hash of a user having all scopes
+    Passhash(String),
+
+    Restricted {
+        passhash: String,
+        scopes: HashSet<Scope>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Seconds an access token is valid
+    pub access: u64,
+
+    /// Seconds a refresh token is valid - refreshing issues a new access and refresh token
+    pub refresh: u64,
+
+    /// Seconds after the login after which a session expires regardless of refreshing
+    pub max: u64,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            access: 15 * 60,
+            refresh: 24 * 60 * 60,
+            max: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Auth {
-    pub passhash: String,
+    /// Name of the account using `passhash` - defaults to `admin`
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Password hash of a single account
+    #[serde(default)]
+    pub passhash: Option<String>,
+
+    /// Password hashes and optional scopes per username
+    #[serde(default)]
+    pub users: HashMap<String, User>,
+
+    /// Path of a YAML file containing additional users in the same format as `users`
+    #[serde(default)]
+    pub users_file: Option<String>,
 
     pub secret: String,
 
+    #[serde(default)]
+    pub session: Session,
+
+    /// Static API keys as password hashes per username - further keys can be created at runtime
+    #[serde(default)]
     pub api_keys: HashMap<String, String>,
 }
 
@@ -47,13 +103,55 @@ pub enum Juicer {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DumbSuggester {
-    pub path: String,
+    /// Path of a model stored by previous versions outside of the repository - migrated if no model exists yet
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    German,
+    English,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pipeline {
+    /// Languages for stopword removal and stemming - the language of each text is guessed from its stopwords
+    pub languages: Vec<Language>,
+
+    /// Reduce words to their stem
+    pub stemming: bool,
+
+    /// Drop tokens shorter than this number of characters
+    pub min_length: usize,
+
+    /// Replace all numbers by a single token
+    pub mask_numbers: bool,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            languages: vec![Language::German, Language::English],
+            stemming: true,
+            min_length: 3,
+            mask_numbers: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct BayesicSuggester {
-    pub path: String,
+    /// Path of a model stored by previous versions outside of the repository - re-trained if no model exists yet
+    #[serde(default)]
+    pub path: Option<String>,
+
     pub certainty: f64,
+
+    #[serde(default)]
+    pub pipeline: Pipeline,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -64,6 +162,49 @@ pub enum Suggester {
     Bayesic(BayesicSuggester),
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Extractor {
+    /// Regular expressions per property key used to find property values in the plaintext
+    #[serde(default)]
+    pub properties: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyType {
+    String,
+    Number,
+
+    /// A date formatted as `YYYY-MM-DD`
+    Date,
+
+    /// An amount with the currency code or symbol (like `12.34 EUR` or `€ 12,34`)
+    Money,
+
+    Enum { values: Vec<String> },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Property {
+    #[serde(flatten)]
+    pub kind: PropertyType,
+
+    /// Labels requiring the property on a document - children of these labels require the property, too
+    #[serde(default)]
+    pub required: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Properties {
+    /// Rejects properties which are not defined
+    #[serde(default)]
+    pub strict: bool,
+
+    #[serde(default)]
+    pub definitions: HashMap<String, Property>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Web {
     pub address: String,
@@ -80,6 +221,12 @@ pub struct Config {
     pub juicer: Juicer,
     pub suggester: Suggester,
 
+    #[serde(default)]
+    pub extractor: Extractor,
+
+    #[serde(default)]
+    pub properties: Properties,
+
     pub web: Web,
 }
 
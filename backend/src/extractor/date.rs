@@ -0,0 +1,131 @@
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+
+/// Month names and abbreviations in German and English.
+const MONTHS: &str = r"jan(?:uar|uary)?|jän(?:ner)?|feb(?:ruar|ruary)?|mär(?:z)?|mar(?:ch)?|apr(?:il)?|mai|may|jun[ei]?|jul[iy]?|aug(?:ust)?|sep(?:t(?:ember)?)?|o[ck]t(?:ober)?|nov(?:ember)?|de[cz](?:ember)?";
+
+/// Converts the captures of a pattern to a date.
+type Parse = fn(&Captures) -> Option<NaiveDate>;
+
+/// The date patterns, each with the function converting its captures to a date.
+pub struct Patterns {
+    patterns: Vec<(Regex, Parse)>,
+}
+
+impl Default for Patterns {
+    fn default() -> Self {
+        return Self {
+            patterns: vec![
+                (Regex::new(r"\b(\d{4})-(\d{1,2})-(\d{1,2})\b").expect("Invalid pattern"),
+                 |c| date(number(&c[1])?, number(&c[2])?, number(&c[3])?)),
+
+                (Regex::new(r"\b(\d{1,2})\.\s?(\d{1,2})\.\s?(\d{4}|\d{2})\b").expect("Invalid pattern"),
+                 |c| date(year(&c[3])?, number(&c[2])?, number(&c[1])?)),
+
+                (Regex::new(&format!(r"(?i)\b(\d{{1,2}})(?:\.|st|nd|rd|th)?\s+({})\.?\s+(\d{{4}})\b", MONTHS)).expect("Invalid pattern"),
+                 |c| date(year(&c[3])?, month(&c[2])?, number(&c[1])?)),
+
+                (Regex::new(&format!(r"(?i)\b({})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?,?\s+(\d{{4}})\b", MONTHS)).expect("Invalid pattern"),
+                 |c| date(year(&c[3])?, month(&c[1])?, number(&c[2])?)),
+            ],
+        };
+    }
+}
+
+impl Patterns {
+    /// Finds candidate dates in the plaintext of a document.
+    ///
+    /// Numeric dates (`2020-03-01`, `01.03.2020`, `1.3.20`) and dates with month names in German and English
+    /// (`1. März 2020`, `1st March 2020`, `March 1, 2020`) are recognized. The candidates are returned in order of their
+    /// first occurrence without duplicates.
+    pub fn extract(&self, text: &str) -> Vec<NaiveDate> {
+        let mut candidates = self.patterns.iter()
+            .flat_map(|(pattern, parse)| pattern.captures_iter(text)
+                .filter_map(move |captures| {
+                    let start = captures.get(0)?.start();
+                    return parse(&captures).map(|date| (start, date));
+                }))
+            .collect::<Vec<_>>();
+
+        candidates.sort_by_key(|(start, _)| *start);
+
+        let mut dates = Vec::new();
+        for (_, date) in candidates {
+            if !dates.contains(&date) {
+                dates.push(date);
+            }
+        }
+
+        return dates;
+    }
+}
+
+fn number(s: &str) -> Option<u32> {
+    return s.parse().ok();
+}
+
+/// Parses a year with two-digit years being mapped to 1970 - 2069.
+fn year(s: &str) -> Option<u32> {
+    let year = number(s)?;
+
+    return Some(match (s.len(), year) {
+        (2, year) if year < 70 => 2000 + year,
+        (2, year) => 1900 + year,
+        (_, year) => year,
+    });
+}
+
+fn month(s: &str) -> Option<u32> {
+    let prefix = s.to_lowercase().chars().take(3).collect::<String>();
+
+    return match prefix.as_str() {
+        "jan" | "jän" => Some(1),
+        "feb" => Some(2),
+        "mär" | "mar" => Some(3),
+        "apr" => Some(4),
+        "mai" | "may" => Some(5),
+        "jun" => Some(6),
+        "jul" => Some(7),
+        "aug" => Some(8),
+        "sep" => Some(9),
+        "okt" | "oct" => Some(10),
+        "nov" => Some(11),
+        "dez" | "dec" => Some(12),
+        _ => None,
+    };
+}
+
+fn date(year: u32, month: u32, day: u32) -> Option<NaiveDate> {
+    // Ignore anything not looking like a reasonable document date
+    if !(1900..=2100).contains(&year) {
+        return None;
+    }
+
+    return NaiveDate::from_ymd_opt(year as i32, month, day);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let text = "Rechnung vom 03.02.2020\n\
+                    Lieferdatum: 1. März 2020, fällig am 15.3.20\n\
+                    Invoice date: March 4th, 2020 - delivered on 2nd Feb. 2020\n\
+                    Created 2020-02-03 at 99.99.2020 with version 1.2.3";
+
+        assert_eq!(Patterns::default().extract(text), vec![
+            NaiveDate::from_ymd(2020, 2, 3),
+            NaiveDate::from_ymd(2020, 3, 1),
+            NaiveDate::from_ymd(2020, 3, 15),
+            NaiveDate::from_ymd(2020, 3, 4),
+            NaiveDate::from_ymd(2020, 2, 2),
+        ]);
+    }
+
+    #[test]
+    fn test_extract_none() {
+        assert!(Patterns::default().extract("Version 1.2.3 costs 12.50 EUR").is_empty());
+    }
+}
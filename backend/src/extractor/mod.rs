@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::config::Extractor as Config;
+
+pub mod date;
+pub mod property;
+pub mod title;
+
+/// Extracts metadata proposals from the plaintext of a document.
+pub struct Extractor {
+    properties: Vec<property::Rule>,
+    dates: date::Patterns,
+    titles: title::Patterns,
+}
+
+impl Extractor {
+    pub fn from_config(config: Config) -> Result<Self> {
+        let properties = config.properties.into_iter()
+            .map(|(key, patterns)| property::Rule::new(key, &patterns))
+            .collect::<Result<_>>()?;
+
+        return Ok(Self {
+            properties,
+            dates: date::Patterns::default(),
+            titles: title::Patterns::default(),
+        });
+    }
+
+    pub fn dates(&self, text: &str) -> Vec<NaiveDate> {
+        return self.dates.extract(text);
+    }
+
+    pub fn properties(&self, text: &str) -> HashMap<String, Vec<String>> {
+        return property::extract(&self.properties, text);
+    }
+
+    /// Proposes a title using the first proposal of the `sender` property as sender, if configured.
+    pub fn title(&self, text: &str) -> Option<String> {
+        let sender = self.properties.iter()
+            .find(|rule| rule.key() == "sender")
+            .and_then(|rule| rule.extract(text).into_iter().next());
+
+        return self.titles.extract(text, sender.as_deref());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// A rule proposing values for a property.
+///
+/// Each pattern is matched against the plaintext. The first capture group of a match is proposed as value, or the whole
+/// match if the pattern has no capture groups.
+pub struct Rule {
+    key: String,
+    patterns: Vec<Regex>,
+}
+
+impl Rule {
+    pub fn new(key: String, patterns: &[String]) -> Result<Self> {
+        let patterns = patterns.iter()
+            .map(|pattern| Regex::new(pattern)
+                .with_context(|| format!("Invalid pattern for property {}: {}", key, pattern)))
+            .collect::<Result<_>>()?;
+
+        return Ok(Self { key, patterns });
+    }
+
+    pub fn key(&self) -> &str { return &self.key; }
+
+    /// Proposes values in order of the patterns and their occurrence without duplicates.
+    pub fn extract(&self, text: &str) -> Vec<String> {
+        let mut values = Vec::new();
+
+        for pattern in &self.patterns {
+            for captures in pattern.captures_iter(text) {
+                let value = captures.get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|value| value.as_str().trim().to_string())
+                    .unwrap_or_default();
+
+                if !value.is_empty() && !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+
+        return values;
+    }
+}
+
+/// Applies the rules to the plaintext of a document.
+///
+/// The proposed values are returned per property key. Properties without any proposal are omitted.
+pub fn extract(rules: &[Rule], text: &str) -> HashMap<String, Vec<String>> {
+    let mut properties = HashMap::new();
+
+    for rule in rules {
+        let values = rule.extract(text);
+        if !values.is_empty() {
+            properties.insert(rule.key.clone(), values);
+        }
+    }
+
+    return properties;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let rules = vec![
+            Rule::new(String::from("invoice"), &[
+                String::from(r"(?i)Rechnungsnummer:?\s*(\S+)"),
+                String::from(r"(?i)Invoice No\.?:?\s*(\S+)"),
+            ]).unwrap(),
+            Rule::new(String::from("iban"), &[
+                String::from(r"\bDE\d{2}(?: ?\d{4}){4} ?\d{2}\b"),
+            ]).unwrap(),
+            Rule::new(String::from("amount"), &[
+                String::from(r"(\d+,\d{2}) EUR"),
+            ]).unwrap(),
+        ];
+
+        let text = "Rechnungsnummer: RE-2020-42\n\
+                    Invoice No. RE-2020-42\n\
+                    Bitte überweisen Sie den Betrag auf DE89 3704 0044 0532 0130 00";
+
+        let properties = extract(&rules, text);
+
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["invoice"], vec![String::from("RE-2020-42")]);
+        assert_eq!(properties["iban"], vec![String::from("DE89 3704 0044 0532 0130 00")]);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Rule::new(String::from("broken"), &[String::from("(unclosed")]).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use regex::Regex;
+
+/// Number of leading lines considered when looking for a title.
+const LEADING_LINES: usize = 20;
+
+/// Words indicating the type of a document which makes a line a good title.
+const DOCUMENT_TYPES: &str = r"(?i)\b(rechnung|gutschrift|mahnung|zahlungserinnerung|angebot|auftragsbestätigung|bestellung|lieferschein|quittung|vertrag|kündigung|bescheid|bescheinigung|bestätigung|kontoauszug|abrechnung|invoice|credit note|reminder|quote|quotation|order|receipt|contract|statement|certificate|confirmation)\b";
+
+/// The patterns recognizing lines suitable as title.
+pub struct Patterns {
+    subject: Regex,
+    document_type: Regex,
+}
+
+impl Default for Patterns {
+    fn default() -> Self {
+        return Self {
+            subject: Regex::new(r"(?im)^\s*(?:betreff|betr\.|subject|re|ref)\s*:\s*(.+?)\s*$").expect("Invalid pattern"),
+            document_type: Regex::new(DOCUMENT_TYPES).expect("Invalid pattern"),
+        };
+    }
+}
+
+impl Patterns {
+    /// Proposes a title for a document from its plaintext.
+    ///
+    /// An explicit subject line (`Betreff: ...`, `Subject: ...`) is preferred. Otherwise, the first leading line naming
+    /// a document type is taken, falling back to the first leading line looking like text. If a sender is known, it is
+    /// prepended to the subject.
+    pub fn extract(&self, text: &str, sender: Option<&str>) -> Option<String> {
+        let leading = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .take(LEADING_LINES)
+            .collect::<Vec<_>>();
+
+        let title = self.subject.captures(text)
+            .map(|captures| captures[1].to_string())
+            .or_else(|| leading.iter()
+                .find(|line| prominent(line) && self.document_type.is_match(line))
+                .map(|line| line.to_string()))
+            .or_else(|| leading.iter()
+                .find(|line| prominent(line))
+                .map(|line| line.to_string()))?;
+
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        return Some(match sender {
+            Some(sender) if !title.contains(sender) => format!("{} - {}", sender, title),
+            _ => title,
+        });
+    }
+}
+
+/// Checks if a line looks like a title and not like an address, a date or a table row.
+fn prominent(line: &str) -> bool {
+    let length = line.chars().count();
+    if length < 5 || length > 80 {
+        return false;
+    }
+
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let words = line.split_whitespace().count();
+
+    return words >= 2 && letters * 2 >= length;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_subject() {
+        let text = "Muster GmbH\nMusterstraße 1\n12345 Musterstadt\n\nBetreff:   Ihre   Bestellung 4711\n\nSehr geehrte Damen und Herren,";
+
+        assert_eq!(Patterns::default().extract(text, None), Some(String::from("Ihre Bestellung 4711")));
+        assert_eq!(Patterns::default().extract(text, Some("Muster GmbH")), Some(String::from("Muster GmbH - Ihre Bestellung 4711")));
+    }
+
+    #[test]
+    fn test_document_type() {
+        let text = "Muster GmbH\nMusterstraße 1\n12345 Musterstadt\n01.02.2020\nRechnung Nr. 2020-42\nPos Menge Preis";
+
+        assert_eq!(Patterns::default().extract(text, None), Some(String::from("Rechnung Nr. 2020-42")));
+    }
+
+    #[test]
+    fn test_fallback() {
+        assert_eq!(Patterns::default().extract("12345\n\nMy little document\nmore text", None), Some(String::from("My little document")));
+        assert_eq!(Patterns::default().extract("12345\n2020-01-01", None), None);
+    }
+}
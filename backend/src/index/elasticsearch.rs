@@ -3,47 +3,219 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use elasticsearch::{Elasticsearch, IndexParts, SearchParts};
+use elasticsearch::indices::{IndicesCreateParts, IndicesDeleteParts, IndicesExistsParts, IndicesGetMappingParts, IndicesPutMappingParts};
 use elasticsearch::http::transport::Transport;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::value::{RawValue, Value};
 
-use crate::config::ElasticsearchIndex as Config;
-use crate::index::SearchResponse;
+use crate::config::{ElasticsearchIndex as Config, PropertyType};
+use crate::index::{DateRange, PropertyRange, SearchResponse};
 use crate::proto::model::{DocId, Label};
 use crate::repository::{Archived, Bundle};
+use crate::schema::{Schema, Value as PropertyValue};
 
 const DOCUMENT_TYPE: &str = "document";
 
+/// Version of the indexed document fields.
+///
+/// Documents indexed with another version lack fields and the archive is re-indexed.
+const VERSION: u32 = 1;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Source {
     text: String,
     uploaded: DateTime<Utc>,
     archived: Option<DateTime<Utc>>,
+    date: Option<NaiveDate>,
     labels: HashSet<Label>,
+
+    /// All labels including their parents to find documents by parent labels
+    hierarchy: HashSet<Label>,
+
     properties: HashMap<String, String>,
+
+    /// The defined properties converted to their type
+    values: HashMap<String, PropertyValue>,
 }
 
 pub struct Index {
     client: Elasticsearch,
 
     index: String,
+
+    schema: Schema,
+
+    outdated: bool,
 }
 
 impl Index {
-    pub async fn from_config(config: Config) -> Result<Self> {
-        Self::connect(config.url, config.index).await
+    pub async fn from_config(config: Config, schema: Schema) -> Result<Self> {
+        Self::connect(config.url, config.index, schema, false).await
     }
 
-    pub async fn connect(url: String, index: String) -> Result<Self> {
+    /// Connects to the index after deleting it with all indexed documents.
+    pub async fn recreate(config: Config, schema: Schema) -> Result<Self> {
+        Self::connect(config.url, config.index, schema, true).await
+    }
+
+    pub async fn connect(url: String, index: String, schema: Schema, recreate: bool) -> Result<Self> {
         let transport = Transport::single_node(&url)?;
         let client = Elasticsearch::new(transport);
 
         client.ping().send().await?;
 
-        Ok(Self { client, index })
+        let mut index = Self { client, index, schema, outdated: false };
+
+        if recreate {
+            index.delete().await?;
+        }
+
+        index.outdated = index.map().await?;
+
+        Ok(index)
+    }
+
+    async fn delete(&self) -> Result<()> {
+        info!("Deleting ElasticSearch index {}", self.index);
+
+        let response = self.client.indices()
+            .delete(IndicesDeleteParts::Index(&[&self.index]))
+            .send().await?;
+
+        if !response.status_code().is_success() && response.status_code().as_u16() != 404 {
+            return Err(anyhow!(
+                "ElasticSearch index deletion error: {}",
+                response.read_body::<Box<RawValue>>().await?
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The description of the indexed fields stored along with the mapping.
+    ///
+    /// Documents indexed before a property was defined lack its typed value and must be re-indexed.
+    fn meta(&self) -> Value {
+        return json!({
+            "version": VERSION,
+            "values": self.values(),
+        });
+    }
+
+    /// The field types of the typed property values.
+    fn values(&self) -> serde_json::Map<String, Value> {
+        return self.schema.kinds()
+            .map(|(key, kind)| (key.to_string(), match kind {
+                PropertyType::String => json!({ "type": "text" }),
+                PropertyType::Number => json!({ "type": "double" }),
+                PropertyType::Date => json!({ "type": "date", "format": "yyyy-MM-dd" }),
+                PropertyType::Money => json!({
+                    "properties": {
+                        "amount": { "type": "double" },
+                        "currency": { "type": "keyword" },
+                    }
+                }),
+                PropertyType::Enum { .. } => json!({ "type": "keyword" }),
+            }))
+            .collect();
+    }
+
+    /// Maps the typed property values to their according field types.
+    ///
+    /// Returns whether the documents have been indexed by another version or schema.
+    async fn map(&self) -> Result<bool> {
+        let values = self.values();
+
+        let exists = self.client.indices()
+            .exists(IndicesExistsParts::Index(&[&self.index]))
+            .send().await?;
+
+        if !exists.status_code().is_success() {
+            let response = self.client.indices()
+                .create(IndicesCreateParts::Index(&self.index))
+                .send().await?;
+
+            if !response.status_code().is_success() {
+                return Err(anyhow!(
+                    "ElasticSearch index creation error: {}",
+                    response.read_body::<Box<RawValue>>().await?
+                ));
+            }
+        }
+
+        let response = self.client.indices()
+            .get_mapping(IndicesGetMappingParts::Index(&[&self.index]))
+            .send().await?;
+
+        if !response.status_code().is_success() {
+            return Err(anyhow!(
+                "ElasticSearch mapping error: {}",
+                response.read_body::<Box<RawValue>>().await?
+            ));
+        }
+
+        let mappings = response.read_body::<Value>().await?;
+        let mapping = mappings.as_object()
+            .and_then(|indices| indices.values().next())
+            .map(|index| index["mappings"].clone())
+            .unwrap_or(Value::Null);
+
+        // The type of a mapped field can not be changed without re-creating the index
+        let date = json!({ "type": "date", "format": "yyyy-MM-dd" });
+        match mapping["properties"].get("date") {
+            Some(existing) if existing != &date => {
+                return Err(anyhow!(
+                    "Type of the document date has changed from {} to {} - run `adacta reindex` to re-create the index",
+                    existing, date
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(mapped) = mapping["properties"]["values"]["properties"].as_object() {
+            for (key, field) in &values {
+                match mapped.get(key) {
+                    Some(existing) if existing != field => {
+                        return Err(anyhow!(
+                            "Type of property {} has changed from {} to {} - run `adacta reindex` to re-create the index",
+                            key, existing, field
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let outdated = mapping["_meta"] != self.meta();
+        if outdated {
+            warn!("ElasticSearch index {} has been built by another version or schema: {}", self.index, mapping["_meta"]);
+        }
+
+        let response = self.client.indices()
+            .put_mapping(IndicesPutMappingParts::IndexType(&[&self.index], &[DOCUMENT_TYPE]))
+            .include_type_name(true)
+            .body(json!({
+                "properties": {
+                    "date": date,
+                    "values": {
+                        "properties": values
+                    }
+                }
+            }))
+            .send().await?;
+
+        if !response.status_code().is_success() {
+            return Err(anyhow!(
+                "ElasticSearch mapping error: {}",
+                response.read_body::<Box<RawValue>>().await?
+            ));
+        }
+
+        Ok(outdated)
     }
 
     async fn query(&self, mut query: Value) -> Result<SearchResponse> {
@@ -93,7 +265,10 @@ impl super::Index for Index {
                 text,
                 uploaded: meta.uploaded,
                 archived: meta.archived,
+                date: meta.date,
+                hierarchy: meta.labels.iter().flat_map(Label::ancestors).collect(),
                 labels: meta.labels,
+                values: self.schema.typed(&meta.properties),
                 properties: meta.properties,
             })
             .send().await?;
@@ -101,7 +276,86 @@ impl super::Index for Index {
         Ok(())
     }
 
-    async fn search(&self, query: &str) -> Result<SearchResponse> {
+    async fn requires_reindex(&self) -> bool {
+        return self.outdated;
+    }
+
+    async fn reindex<'r>(&self, bundles: &[Bundle<'r, Archived>]) -> Result<()> {
+        for bundle in bundles {
+            super::Index::index(self, bundle).await?;
+        }
+
+        // Mark the index as up to date only after all documents have been indexed to continue after failures
+        let response = self.client.indices()
+            .put_mapping(IndicesPutMappingParts::IndexType(&[&self.index], &[DOCUMENT_TYPE]))
+            .include_type_name(true)
+            .body(json!({
+                "_meta": self.meta(),
+            }))
+            .send().await?;
+
+        if !response.status_code().is_success() {
+            return Err(anyhow!(
+                "ElasticSearch mapping error: {}",
+                response.read_body::<Box<RawValue>>().await?
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn search(&self, query: &str, dates: &DateRange, properties: &[PropertyRange], labels: &HashSet<Label>) -> Result<SearchResponse> {
+        let mut filter = Vec::new();
+
+        if dates.from.is_some() || dates.to.is_some() {
+            let mut range = json!({});
+
+            if let Some(from) = dates.from {
+                range["gte"] = json!(from);
+            }
+
+            if let Some(to) = dates.to {
+                range["lte"] = json!(to);
+            }
+
+            filter.push(json!({
+                "range": {
+                    "date": range
+                }
+            }));
+        }
+
+        for property in properties {
+            let field = match self.schema.kind(&property.key) {
+                Some(PropertyType::Money) => format!("values.{}.amount", property.key),
+                _ => format!("values.{}", property.key),
+            };
+
+            let mut range = json!({});
+
+            if let Some(min) = &property.min {
+                range["gte"] = json!(min);
+            }
+
+            if let Some(max) = &property.max {
+                range["lte"] = json!(max);
+            }
+
+            filter.push(json!({
+                "range": {
+                    field: range
+                }
+            }));
+        }
+
+        for label in labels {
+            filter.push(json!({
+                "term": {
+                    "hierarchy.keyword": label
+                }
+            }));
+        }
+
         self.query(json!({
             "query": {
                 "bool" : {
@@ -109,7 +363,8 @@ impl super::Index for Index {
                         "simple_query_string" : {
                             "query" : query
                         }
-                    }
+                    },
+                    "filter": filter
                 }
             }
         })).await
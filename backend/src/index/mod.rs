@@ -1,10 +1,15 @@
+use std::collections::HashSet;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::NaiveDate;
+use log::info;
 #[cfg(test)]
 use mockall::automock;
 
-use crate::proto::model::DocId;
-use crate::repository::{Archived, Bundle};
+use crate::proto::model::{DocId, Label};
+use crate::repository::{Archived, Bundle, Repository};
+use crate::schema::Value;
 
 pub mod elasticsearch;
 
@@ -14,9 +19,43 @@ pub struct SearchResponse {
     pub docs: Vec<DocId>,
 }
 
+/// Restricts search results to documents dated within the range (both ends inclusive).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Restricts search results to documents with a typed property value within the range (both ends inclusive).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyRange {
+    pub key: String,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+}
+
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Index {
     async fn index<'r>(&self, bundle: &Bundle<'r, Archived>) -> Result<()>;
-    async fn search(&self, query: &str) -> Result<SearchResponse>;
+
+    /// Whether the documents have been indexed by an incompatible version and the archive must be re-indexed.
+    async fn requires_reindex(&self) -> bool;
+
+    /// Indexes all given documents again and marks the index as up to date.
+    async fn reindex<'r>(&self, bundles: &[Bundle<'r, Archived>]) -> Result<()>;
+
+    /// Searches for documents matching the query.
+    ///
+    /// Only documents having all given labels or any of their children are found.
+    async fn search(&self, query: &str, dates: &DateRange, properties: &[PropertyRange], labels: &HashSet<Label>) -> Result<SearchResponse>;
+}
+
+/// Indexes all documents in the archive again.
+pub async fn reindex(index: &(dyn Index + Send + Sync), repository: &Repository) -> Result<()> {
+    let bundles = repository.archive().list().await?;
+
+    info!("Indexing {} archived documents", bundles.len());
+
+    return index.reindex(&bundles).await;
 }
@@ -1,15 +1,17 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use futures::{StreamExt, TryStreamExt};
+use futures::{future, StreamExt, TryStreamExt};
 use log::{debug, error, trace};
 use shiplift::{ContainerOptions, Docker, LogsOptions};
 use tokio::io::AsyncWriteExt;
 
 use crate::config::DockerJuicer as Config;
+use crate::juicer::{sanitize_path, ValidationError};
 use crate::proto::model::Kind;
 use crate::repository::{Bundle, Staging};
 use std::path::Path;
 use std::io::Cursor;
+use tar::EntryType;
 
 #[cfg(test)]
 mod test;
@@ -41,6 +43,13 @@ impl super::Juicer for Juicer {
         let mut logfile = bundle.write(Kind::other("juicer.log")).await
             .with_context(|| "Failed to open juicer.log")?;
 
+        // Collect all fragments from the bundle
+        let fragments = tokio::fs::read_dir(bundle.path()).await?
+            .map_ok(|entry| entry.file_name())
+            .try_filter(|name| future::ready(name != "juicer.log"))
+            .try_collect::<Vec<_>>().await
+            .context("Error listing fragments")?;
+
         let containers = self.docker.containers();
 
         debug!("Creating container");
@@ -55,8 +64,9 @@ impl super::Juicer for Juicer {
         debug!("Uploading bundle to container (id={})", container.id());
         let upload: Result<_> = try {
             let mut archive = tar::Builder::new(Vec::new());
-            archive.append_path_with_name(bundle.path_of(Kind::Metadata), "metadata.json")?;
-            archive.append_path_with_name(bundle.path_of(Kind::other("original.pdf")), "original.pdf")?;
+            for fragment in fragments {
+                archive.append_path_with_name(bundle.path().join(&fragment), &fragment)?;
+            }
             archive.into_inner()?
         };
         let upload = upload.context("Error creating upload archive")?;
@@ -87,25 +97,33 @@ impl super::Juicer for Juicer {
 
         debug!("Downloading bundle to container (id={})", container.id());
         let download = container.copy_from(Path::new("/juicer/"))
-            .try_concat().await?;
-
-        // Extract the received tar archive into the bundle folder
-        // TODO fooker: add context for error handling
-        let mut tar = tar::Archive::new(Cursor::new(download));
-        for entry in tar.entries()? {
-            let mut entry = entry?;
-
-            let path = entry.path()?;
-            let path = path.strip_prefix("juicer/")?;
-            let path = bundle.path().join(path);
-
-            entry.unpack(path)?;
-        }
+            .try_concat().await
+            .with_context(|| format!("Error downloading bundle from container (id={})", container.id()))?;
 
         debug!("Deleting container (id={})", container.id());
         container.delete().await
             .with_context(|| format!("Error deleting container (id={})", container.id()))?;
 
+        // Extract the received tar archive into the bundle folder
+        let mut tar = tar::Archive::new(Cursor::new(download));
+        for entry in tar.entries().context("Error reading juicer output")? {
+            let mut entry = entry.context("Error reading juicer output")?;
+
+            // Only accept files and directories inside of the bundle folder
+            let path = entry.path()?.into_owned();
+            let path = path.strip_prefix("juicer/")
+                .map_err(|_| ValidationError::IllegalPath(path.clone()))?;
+            let path = sanitize_path(path)?;
+
+            match entry.header().entry_type() {
+                EntryType::Regular | EntryType::Directory => {}
+                _ => return Err(ValidationError::IllegalEntry(path).into()),
+            }
+
+            entry.unpack(bundle.path().join(&path))
+                .with_context(|| format!("Error extracting juicer output: {:?}", path))?;
+        }
+
         // Fail with error depending on status-code
         if result.status_code != 0 {
             error!("Container failed (id={}): {}", container.id(), result.status_code);
@@ -0,0 +1,52 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use spectral::prelude::*;
+
+use crate::juicer::{Assembly, Juicer as _, Part};
+use crate::meta::Metadata;
+use crate::repository::Repository;
+
+use super::*;
+use log::LevelFilter;
+
+#[tokio::test]
+async fn test_assemble_interleaved_reversed() {
+    let _ = env_logger::builder().filter_module("adacta", LevelFilter::Trace).is_test(true).try_init();
+
+    let repository = Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+    let juicer = juicer().await.unwrap();
+
+    let bundle = repository.stage().await.unwrap();
+
+    Metadata {
+        uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+        ..Metadata::new()
+    }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+    // The front-sides are scanned in order while the back-sides are scanned in reverse order
+    bundle.write(Kind::other("original-1.pdf")).await.unwrap()
+        .write_all(&Resources::get("assemble_front.pdf").unwrap()).await.unwrap();
+    bundle.write(Kind::other("original-2.pdf")).await.unwrap()
+        .write_all(&Resources::get("assemble_back.pdf").unwrap()).await.unwrap();
+
+    Assembly {
+        interleave: true,
+        oriented: false,
+        parts: vec![
+            Part { source: String::from("original-1.pdf"), pages: None, rotate: None },
+            Part { source: String::from("original-2.pdf"), pages: Some(String::from("z-1")), rotate: None },
+        ],
+    }.save(&bundle).await.unwrap();
+
+    juicer.extract(&bundle).await.unwrap();
+
+    assert_that!(bundle.read_metadata().await.unwrap().pages).is_equal_to(4);
+
+    let plaintext = tokio::fs::read_to_string(bundle.path_of(Kind::Plaintext)).await.unwrap();
+    let positions = ["one", "two", "three", "four"].iter()
+        .map(|page| plaintext.find(&format!("This is page {}", page)).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut sorted = positions.clone();
+    sorted.sort();
+    assert_that!(positions).is_equal_to(sorted);
+}
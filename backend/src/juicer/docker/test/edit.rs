@@ -0,0 +1,56 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use spectral::prelude::*;
+
+use crate::juicer::{edit, juice, Juicer as _};
+use crate::meta::Metadata;
+use crate::proto::model::PageEdit;
+use crate::repository::Repository;
+
+use super::*;
+use log::LevelFilter;
+
+/// Reads the width and height of a PNG image from its header.
+fn dimensions(png: &[u8]) -> (u32, u32) {
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+
+    return (width, height);
+}
+
+#[tokio::test]
+async fn test_edit_rotated_scan() {
+    let _ = env_logger::builder().filter_module("adacta", LevelFilter::Trace).is_test(true).try_init();
+
+    let repository = Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+    let juicer = juicer().await.unwrap();
+
+    let metadata = Metadata {
+        uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+        ..Metadata::new()
+    };
+
+    // Render a page of text to get a scanned image without any text which must be OCRed
+    let rendered = upload(&repository, metadata.clone(), "edit_rotate.pdf").await.unwrap();
+    juicer.extract(&rendered).await.unwrap();
+    let scan = tokio::fs::read(rendered.path_of(Kind::page_preview(1))).await.unwrap();
+
+    let bundle = repository.stage().await.unwrap();
+    metadata.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+    bundle.write(Kind::other("original.png")).await.unwrap()
+        .write_all(&scan).await.unwrap();
+
+    juice(&juicer, &bundle).await.unwrap();
+    let bundle = bundle.create().await.unwrap();
+
+    let (width, height) = dimensions(&tokio::fs::read(bundle.path_of(Kind::page_preview(1))).await.unwrap());
+    assert_that!(width).is_less_than(height);
+
+    let bundle = edit(&juicer, bundle, &[PageEdit { page: 1, rotate: 90 }]).await.unwrap();
+
+    // The automatic rotation while juicing again does not turn the page back upright
+    let (width, height) = dimensions(&tokio::fs::read(bundle.path_of(Kind::page_preview(1))).await.unwrap());
+    assert_that!(width).is_greater_than(height);
+
+    let plaintext = tokio::fs::read_to_string(bundle.path_of(Kind::Plaintext)).await.unwrap();
+    assert_that!(plaintext).contains("orientation");
+}
@@ -76,4 +76,26 @@ async fn test_extract_with_title_conflicting() {
     juicer.extract(&bundle).await.unwrap();
 
     assert_that!(bundle.read_metadata().await.unwrap().title).is_equal_to(Some(String::from("Some existing title")));
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_extract_transparent_image() {
+    let _ = env_logger::builder().filter_module("adacta", LevelFilter::Trace).is_test(true).try_init();
+
+    let repository = Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+    let juicer = juicer().await.unwrap();
+
+    let bundle = repository.stage().await.unwrap();
+
+    Metadata {
+        uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+        ..Metadata::new()
+    }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+    // The image has an alpha channel which can not be converted to PDF as is
+    bundle.write(Kind::other("original.png")).await.unwrap()
+        .write_all(&Resources::get("extract_alpha.png").unwrap()).await.unwrap();
+
+    juicer.extract(&bundle).await.unwrap();
+
+    assert_that!(bundle.read_metadata().await.unwrap().pages).is_equal_to(1);
+}
@@ -7,6 +7,8 @@ use crate::repository::Repository;
 
 use super::*;
 
+mod assemble;
+mod edit;
 mod extract;
 
 #[derive(RustEmbed, Debug, Clone)]
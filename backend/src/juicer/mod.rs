@@ -1,14 +1,323 @@
-use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 #[cfg(test)]
 use mockall::automock;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 
-use crate::repository::{Bundle, Staging};
+use crate::meta::Metadata;
+use crate::proto::model::{Kind, PageEdit, PageRange};
+use crate::repository::{Bundle, BundleState, Inboxed, Repository, Staging};
 
 pub mod docker;
 
+/// Name of the fragment passing assembly instructions to the juicer.
+const ASSEMBLY: &str = "assemble.json";
+
+/// Name of the fragment holding the rearranged pages of the processed document after editing the pages of a bundle.
+const EDITED: &str = "edited.pdf";
+
+/// Fragments holding the pages of the uploaded document, in order of preference.
+const SOURCES: &[&str] = &[EDITED, "original.pdf", "original.jpg", "original.png", "original.tiff"];
+
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    #[error("Illegal path in juicer output: {0:?}")]
+    IllegalPath(PathBuf),
+
+    #[error("Illegal entry in juicer output: {0:?}")]
+    IllegalEntry(PathBuf),
+
+    #[error("Fragment missing in juicer output: {0:?}")]
+    MissingFragment(PathBuf),
+
+    #[error("Invalid metadata in juicer output: {0}")]
+    InvalidMetadata(String),
+}
+
+/// A part of a document assembled from multiple sources.
+#[derive(Debug, Clone, Serialize)]
+pub struct Part {
+    /// The fragment name of the source document
+    pub source: String,
+
+    /// The range of pages taken from the source document - all pages if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<String>,
+
+    /// The clockwise rotation of the pages in degrees - not rotated if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotate: Option<u16>,
+}
+
+/// Instructs the juicer to assemble the original document from multiple parts.
+///
+/// The parts are converted to PDF and concatenated in order. If `interleave` is set, the pages are taken alternately
+/// from each part instead (first page of each part, then second page of each part, and so on) which allows to merge
+/// the front- and back-sides of duplex scans. The page ranges of the parts are applied before interleaving, which
+/// allows to reverse the back-sides scanned in reverse order using the range `z-1`.
+///
+/// If `oriented` is set, the pages have been oriented by the reviewer and are not rotated automatically.
+#[derive(Debug, Clone, Serialize)]
+pub struct Assembly {
+    pub interleave: bool,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub oriented: bool,
+
+    pub parts: Vec<Part>,
+}
+
+impl Assembly {
+    pub async fn save<'r>(&self, bundle: &Bundle<'r, Staging>) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+
+        bundle.write(Kind::other(ASSEMBLY)).await?
+            .write_all(&data).await?;
+
+        return Ok(());
+    }
+}
+
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Juicer {
     async fn extract<'r>(&self, bundle: &Bundle<'r, Staging>) -> Result<()>;
 }
+
+/// Runs the juicer over a staged bundle and validates the result.
+pub async fn juice<'r>(juicer: &(dyn Juicer + Send + Sync), bundle: &Bundle<'r, Staging>) -> Result<()> {
+    juicer.extract(bundle).await?;
+
+    // Assembly instructions are only required while juicing
+    bundle.remove(Kind::other(ASSEMBLY)).await?;
+
+    validate(bundle).await
+        .context("Juicer produced an invalid bundle")?;
+
+    return Ok(());
+}
+
+/// Finds the fragment holding the uploaded pages of the document of a bundle and returns it with its extension.
+///
+/// This is the uploaded document as long as the pages of the bundle have not been edited. Bundles without an uploaded
+/// document fall back to the processed document.
+async fn source_of<'r, S: BundleState>(bundle: &Bundle<'r, S>) -> Result<(Kind, &'static str)> {
+    for name in SOURCES {
+        let kind = Kind::other(*name);
+        match tokio::fs::metadata(bundle.path_of(&kind)).await {
+            Ok(metadata) if metadata.is_file() => {
+                let extension = name.rsplit('.').next().unwrap_or("pdf");
+                return Ok((kind, extension));
+            }
+            _ => continue,
+        }
+    }
+
+    return Ok((Kind::Document, "pdf"));
+}
+
+/// Assembles a new inboxed bundle from the pages of existing bundles.
+///
+/// The uploaded documents of the source bundles are copied to a new staging bundle and the selected pages are
+/// concatenated by the juicer. The source bundles are left untouched.
+pub async fn assemble<'r>(juicer: &(dyn Juicer + Send + Sync),
+                          repository: &'r Repository,
+                          metadata: &Metadata,
+                          sources: &[(&Bundle<'r, Inboxed>, Option<PageRange>)]) -> Result<Bundle<'r, Inboxed>> {
+    let staging = repository.stage().await?;
+
+    let result: Result<()> = async {
+        let mut parts = Vec::with_capacity(sources.len());
+        for (i, (source, pages)) in sources.iter().enumerate() {
+            let (kind, extension) = source_of(*source).await?;
+
+            let name = format!("source-{}.{}", i + 1, extension);
+            staging.import(*source, kind, Kind::other(&name)).await?;
+
+            parts.push(Part { source: name, pages: pages.map(|pages| pages.to_string()), rotate: None });
+        }
+
+        metadata.save(staging.write(Kind::Metadata).await?).await?;
+
+        let sources = parts.iter()
+            .map(|part| part.source.clone())
+            .collect::<Vec<_>>();
+
+        Assembly { interleave: false, oriented: false, parts }.save(&staging).await?;
+
+        juice(juicer, &staging).await?;
+
+        // The copied source documents are not part of the assembled bundle
+        for source in sources {
+            staging.remove(Kind::other(source)).await?;
+        }
+
+        return Ok(());
+    }.await;
+
+    match result {
+        Ok(()) => {
+            return staging.create().await;
+        }
+        Err(err) => {
+            staging.delete().await?;
+            return Err(err);
+        }
+    }
+}
+
+/// Rearranges the pages of an inboxed bundle.
+///
+/// The document is re-assembled from the given pages of the processed document, so the rotations are applied to the
+/// pages as shown to the reviewer and the juicer keeps their orientation. All derived fragments are replaced by the
+/// juicer output. The rearranged pages are kept as separate fragment while the original fragments are kept untouched.
+pub async fn edit<'r>(juicer: &(dyn Juicer + Send + Sync),
+                      bundle: Bundle<'r, Inboxed>,
+                      pages: &[PageEdit]) -> Result<Bundle<'r, Inboxed>> {
+    let staging = bundle.stage().await?;
+
+    let result: Result<()> = async {
+        let source = String::from("source.pdf");
+        staging.import(&bundle, Kind::Document, Kind::other(&source)).await?;
+
+        let metadata = bundle.read_metadata().await?;
+        metadata.save(staging.write(Kind::Metadata).await?).await?;
+
+        let parts = pages.iter()
+            .map(|edit| Part {
+                source: source.clone(),
+                pages: Some(PageRange::single(edit.page).to_string()),
+                rotate: Some(edit.rotate).filter(|rotate| *rotate != 0),
+            })
+            .collect();
+
+        Assembly { interleave: false, oriented: true, parts }.save(&staging).await?;
+
+        juice(juicer, &staging).await?;
+
+        // The assembled document replaces the previously edited pages - the originals of the bundle are kept instead
+        staging.import(&staging, Kind::other("original.pdf"), Kind::other(EDITED)).await?;
+        staging.remove(Kind::other(&source)).await?;
+        staging.remove(Kind::other("original.pdf")).await?;
+
+        for name in bundle.files().await? {
+            if name.to_string_lossy().starts_with("original") {
+                staging.import(&bundle, Kind::other(&name), Kind::other(&name)).await?;
+            }
+        }
+
+        return Ok(());
+    }.await;
+
+    match result {
+        Ok(()) => {
+            return staging.replace(bundle).await;
+        }
+        Err(err) => {
+            staging.delete().await?;
+            return Err(err);
+        }
+    }
+}
+
+/// Resolves a path received from the juicer relative to the bundle directory.
+///
+/// Paths must be relative and must not contain any parent directory references to ensure the juicer can not write
+/// outside of the bundle.
+pub fn sanitize_path(path: &Path) -> Result<PathBuf, ValidationError> {
+    let mut sanitized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(component) => sanitized.push(component),
+            Component::CurDir => {}
+            _ => return Err(ValidationError::IllegalPath(path.to_path_buf())),
+        }
+    }
+
+    return Ok(sanitized);
+}
+
+/// Validates the fragments produced by the juicer before a staged bundle is accepted.
+///
+/// All fragments required to review and archive the document must exist and the metadata must be parseable.
+pub async fn validate<'r>(bundle: &Bundle<'r, Staging>) -> Result<(), ValidationError> {
+    for kind in &[Kind::Document, Kind::Plaintext, Kind::Preview, Kind::Metadata] {
+        require_fragment(bundle, kind).await?;
+    }
+
+    let metadata = bundle.read_metadata().await
+        .map_err(|err| ValidationError::InvalidMetadata(format!("{:#}", err)))?;
+
+    if metadata.pages == 0 {
+        return Err(ValidationError::InvalidMetadata(String::from("Document has no pages")));
+    }
+
+    // Each page must have a preview and a thumbnail
+    for page in 1..=metadata.pages {
+        require_fragment(bundle, &Kind::page_preview(page)).await?;
+        require_fragment(bundle, &Kind::thumbnail(page)).await?;
+    }
+
+    return Ok(());
+}
+
+async fn require_fragment<'r>(bundle: &Bundle<'r, Staging>, kind: &Kind) -> Result<(), ValidationError> {
+    let path = bundle.path_of(kind);
+
+    match tokio::fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_file() => Ok(()),
+        _ => Err(ValidationError::MissingFragment(path.file_name().map(PathBuf::from).unwrap_or(path))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::meta::Metadata;
+    use crate::repository::Repository;
+
+    use super::*;
+
+    #[test]
+    fn test_sanitize_path() {
+        assert_eq!(sanitize_path(Path::new("document.pdf")).unwrap(), PathBuf::from("document.pdf"));
+        assert_eq!(sanitize_path(Path::new("./document.pdf")).unwrap(), PathBuf::from("document.pdf"));
+        assert_eq!(sanitize_path(Path::new("")).unwrap(), PathBuf::new());
+
+        assert!(sanitize_path(Path::new("../document.pdf")).is_err());
+        assert!(sanitize_path(Path::new("sub/../../document.pdf")).is_err());
+        assert!(sanitize_path(Path::new("/etc/passwd")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let repository = Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+        let bundle = repository.stage().await.unwrap();
+
+        for kind in &[Kind::Document, Kind::Plaintext, Kind::Preview] {
+            bundle.write(kind.clone()).await.unwrap();
+        }
+
+        assert!(matches!(validate(&bundle).await, Err(ValidationError::MissingFragment(_))));
+
+        bundle.write(Kind::Metadata).await.unwrap()
+            .write_all(b"{ \"pages\": \"many\" }").await.unwrap();
+        assert!(matches!(validate(&bundle).await, Err(ValidationError::InvalidMetadata(_))));
+
+        Metadata { pages: 0, ..Metadata::new() }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+        assert!(matches!(validate(&bundle).await, Err(ValidationError::InvalidMetadata(_))));
+
+        Metadata { pages: 3, ..Metadata::new() }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+        assert!(matches!(validate(&bundle).await, Err(ValidationError::MissingFragment(_))));
+
+        for page in 1..=3 {
+            bundle.write(Kind::page_preview(page)).await.unwrap();
+            bundle.write(Kind::thumbnail(page)).await.unwrap();
+        }
+        assert!(validate(&bundle).await.is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::proto::api::keys::KeyInfo;
+use crate::proto::model::Scope;
+use crate::repository::Repository;
+use crate::store::{self, Store};
+
+/// Name of the key file in the data directory of the repository.
+const FILE: &str = "keys.json";
+
+/// Version of the key file format.
+const FORMAT: u32 = 1;
+
+/// Number of random bytes in a generated secret.
+const SECRET_LENGTH: usize = 24;
+
+/// Cost of hashing a secret - the secrets are random and long enough to not require slow hashing.
+const HASH_COST: u32 = 6;
+
+/// The key file contents.
+#[derive(Debug, Deserialize, Serialize)]
+struct File<K> {
+    version: u32,
+    keys: K,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Key {
+    /// The bcrypt hash of the secret
+    hash: String,
+
+    created: DateTime<Utc>,
+    last_used: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scopes: Option<HashSet<Scope>>,
+}
+
+/// API keys created at runtime.
+///
+/// Only the hashes of the secrets are stored in the data directory of the repository. Creating and revoking a key is
+/// written immediately while the last usage of a key is written in the background.
+pub struct Keys {
+    keys: Store<HashMap<String, Key>>,
+}
+
+impl Keys {
+    pub async fn open(repository: &Repository) -> Result<Self> {
+        let path = repository.data_path();
+        tokio::fs::create_dir_all(&path).await?;
+        let path = path.join(FILE);
+
+        let keys = store::read(&path, Self::decode).await?
+            .unwrap_or_default();
+
+        info!("Loaded {} API keys", keys.len());
+
+        return Ok(Self {
+            keys: Store::new(path, keys, Self::encode),
+        });
+    }
+
+    fn decode(data: &[u8]) -> Result<HashMap<String, Key>> {
+        let file = serde_json::from_slice::<File<HashMap<String, Key>>>(data)?;
+        if file.version != FORMAT {
+            return Err(anyhow!("Unsupported key format {} - expected {}", file.version, FORMAT));
+        }
+
+        return Ok(file.keys);
+    }
+
+    fn encode(keys: &HashMap<String, Key>) -> Result<Vec<u8>> {
+        return Ok(serde_json::to_vec_pretty(&File { version: FORMAT, keys })?);
+    }
+
+    /// Returns all keys ordered by name.
+    pub async fn list(&self) -> Vec<KeyInfo> {
+        let mut keys = self.keys.read().await.iter()
+            .map(|(name, key)| KeyInfo {
+                name: name.clone(),
+                created: key.created,
+                last_used: key.last_used,
+                scopes: key.scopes.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+
+        return keys;
+    }
+
+    /// Creates a new key and returns its secret or `None` if a key with the same name already exists.
+    pub async fn create(&self, name: &str, scopes: Option<HashSet<Scope>>) -> Result<Option<String>> {
+        let mut secret = [0u8; SECRET_LENGTH];
+        OsRng.fill_bytes(&mut secret);
+        let secret = base64::encode_config(secret, base64::URL_SAFE_NO_PAD);
+
+        let key = Key {
+            hash: bcrypt::hash(&secret, HASH_COST)?,
+            created: Utc::now(),
+            last_used: None,
+            scopes,
+        };
+
+        let created = self.keys.update(|keys| {
+            if keys.contains_key(name) {
+                return false;
+            }
+
+            keys.insert(name.to_string(), key);
+            return true;
+        }).await;
+
+        if !created {
+            return Ok(None);
+        }
+
+        self.keys.flush().await?;
+
+        info!("Created API key {}", name);
+
+        return Ok(Some(secret));
+    }
+
+    /// Removes a key and returns whether it existed.
+    pub async fn revoke(&self, name: &str) -> Result<bool> {
+        if self.keys.update(|keys| keys.remove(name)).await.is_none() {
+            return Ok(false);
+        }
+
+        self.keys.flush().await?;
+
+        info!("Revoked API key {}", name);
+
+        return Ok(true);
+    }
+
+    /// Checks the secret of a key and records its usage.
+    ///
+    /// Returns the scopes granted to the key or `None` if the key is not valid.
+    pub async fn verify(&self, name: &str, secret: &str) -> Option<HashSet<Scope>> {
+        let (hash, scopes) = match self.keys.read().await.get(name) {
+            Some(key) => (key.hash.clone(), key.scopes.clone()),
+            None => return None,
+        };
+
+        if !bcrypt::verify(secret, &hash).unwrap_or(false) {
+            return None;
+        }
+
+        self.keys.update(|keys| {
+            if let Some(key) = keys.get_mut(name) {
+                key.last_used = Some(Utc::now());
+            }
+        }).await;
+
+        return Some(scopes.unwrap_or_else(Scope::all));
+    }
+
+    /// Writes pending usage times immediately.
+    pub async fn flush(&self) -> Result<()> {
+        return self.keys.flush().await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let keys = Keys::open(&repository).await.unwrap();
+
+        let scanner = keys.create("scanner", Some(vec![Scope::Upload].into_iter().collect())).await.unwrap().unwrap();
+        let script = keys.create("script", None).await.unwrap().unwrap();
+        assert_ne!(scanner, script);
+
+        // Names are unique
+        assert_eq!(keys.create("scanner", None).await.unwrap(), None);
+
+        assert_eq!(keys.verify("scanner", &scanner).await, Some(vec![Scope::Upload].into_iter().collect()));
+        assert_eq!(keys.verify("script", &script).await, Some(Scope::all()));
+        assert_eq!(keys.verify("scanner", &script).await, None);
+        assert_eq!(keys.verify("unknown", &script).await, None);
+
+        let list = keys.list().await;
+        assert_eq!(list.iter().map(|key| key.name.as_str()).collect::<Vec<_>>(), vec!["scanner", "script"]);
+        assert!(list[0].last_used.is_some());
+        assert!(list[1].last_used.is_some());
+        assert_eq!(list[0].scopes, Some(vec![Scope::Upload].into_iter().collect()));
+
+        assert!(keys.revoke("script").await.unwrap());
+        assert!(!keys.revoke("script").await.unwrap());
+        assert_eq!(keys.verify("script", &script).await, None);
+
+        // The keys are persisted in the repository
+        keys.flush().await.unwrap();
+
+        let keys = Keys::open(&repository).await.unwrap();
+        assert!(keys.verify("scanner", &scanner).await.is_some());
+        assert_eq!(keys.list().await.len(), 1);
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Error, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::proto::model::{DocId, Label, LabelInfo};
+use crate::repository::{Archived, Bundle, Repository};
+use crate::store::{self, Store};
+
+/// Name of the label file in the data directory of the repository.
+const FILE: &str = "labels.json";
+
+/// Version of the label file format.
+const FORMAT: u32 = 1;
+
+/// The label file contents.
+#[derive(Debug, Deserialize, Serialize)]
+struct File<L> {
+    version: u32,
+    labels: L,
+}
+
+/// The archived documents affected by relabeling.
+pub struct Relabeled<'r> {
+    /// The documents which have been changed
+    pub changed: Vec<Bundle<'r, Archived>>,
+
+    /// The documents which could not be changed and keep their labels
+    pub failed: Vec<(DocId, Error)>,
+}
+
+/// Number of archived documents per label.
+#[derive(Debug, Default)]
+struct Counts {
+    /// Documents having the label
+    own: HashMap<Label, u64>,
+
+    /// Documents having the label or any of its children
+    total: HashMap<Label, u64>,
+}
+
+impl Counts {
+    /// Counts a document changing its labels from `previous` to `labels`.
+    fn change(&mut self, previous: &HashSet<Label>, labels: &HashSet<Label>) {
+        fn with_ancestors(labels: &HashSet<Label>) -> HashSet<Label> {
+            return labels.iter().flat_map(Label::ancestors).collect();
+        }
+
+        fn recount(counts: &mut HashMap<Label, u64>, previous: &HashSet<Label>, labels: &HashSet<Label>) {
+            for label in previous.difference(labels) {
+                if let Some(count) = counts.get_mut(label) {
+                    *count -= 1;
+                    if *count == 0 {
+                        counts.remove(label);
+                    }
+                }
+            }
+
+            for label in labels.difference(previous) {
+                *counts.entry(label.clone()).or_insert(0) += 1;
+            }
+        }
+
+        recount(&mut self.own, previous, labels);
+        recount(&mut self.total, &with_ancestors(previous), &with_ancestors(labels));
+    }
+}
+
+/// Registry of all labels in the repository.
+///
+/// The number of documents per label is derived from the metadata of the archived documents, while the details of the
+/// labels are stored in the data directory of the repository and written immediately on every change.
+pub struct Labels {
+    info: Store<HashMap<Label, LabelInfo>>,
+    counts: RwLock<Counts>,
+}
+
+impl Labels {
+    pub async fn open(repository: &Repository) -> Result<Self> {
+        let path = repository.data_path();
+        tokio::fs::create_dir_all(&path).await?;
+        let path = path.join(FILE);
+
+        let info = store::read(&path, Self::decode).await?
+            .unwrap_or_default();
+
+        let mut counts = Counts::default();
+        for bundle in repository.archive().list().await? {
+            counts.change(&HashSet::new(), &bundle.read_metadata().await?.labels);
+        }
+
+        info!("Found {} labels in archive", counts.own.len());
+
+        return Ok(Self {
+            info: Store::new(path, info, Self::encode),
+            counts: RwLock::new(counts),
+        });
+    }
+
+    fn decode(data: &[u8]) -> Result<HashMap<Label, LabelInfo>> {
+        let file = serde_json::from_slice::<File<HashMap<Label, LabelInfo>>>(data)?;
+        if file.version != FORMAT {
+            return Err(anyhow!("Unsupported label format {} - expected {}", file.version, FORMAT));
+        }
+
+        return Ok(file.labels);
+    }
+
+    fn encode(labels: &HashMap<Label, LabelInfo>) -> Result<Vec<u8>> {
+        return Ok(serde_json::to_vec_pretty(&File { version: FORMAT, labels })?);
+    }
+
+    /// Returns all labels used by archived documents, their parents and all labels having details.
+    ///
+    /// Each label comes with the number of documents having the label, the number of documents having the label or any
+    /// of its children and its details.
+    pub async fn all(&self) -> HashMap<Label, (u64, u64, LabelInfo)> {
+        let mut labels = self.info.read().await.iter()
+            .map(|(label, info)| (label.clone(), (0, 0, info.clone())))
+            .collect::<HashMap<_, _>>();
+
+        let counts = self.counts.read().await;
+
+        for (label, &count) in counts.own.iter() {
+            labels.entry(label.clone()).or_default().0 = count;
+        }
+
+        for (label, &total) in counts.total.iter() {
+            labels.entry(label.clone()).or_default().1 = total;
+        }
+
+        return labels;
+    }
+
+    pub async fn get(&self, label: &Label) -> Option<LabelInfo> {
+        return self.info.read().await.get(label).cloned();
+    }
+
+    /// Number of archived documents having the label.
+    pub async fn count(&self, label: &Label) -> u64 {
+        return self.counts.read().await.own.get(label).copied().unwrap_or(0);
+    }
+
+    /// Number of archived documents having the label or any of its children.
+    pub async fn total(&self, label: &Label) -> u64 {
+        return self.counts.read().await.total.get(label).copied().unwrap_or(0);
+    }
+
+    /// Whether the label or one of its children is used by an archived document or has details.
+    pub async fn exists(&self, label: &Label) -> bool {
+        let counts = self.counts.read().await;
+        let info = self.info.read().await;
+
+        return counts.own.keys().chain(info.keys())
+            .any(|other| other == label || other.is_descendant_of(label));
+    }
+
+    /// Registers the labels of a newly archived document.
+    pub async fn add(&self, labels: &HashSet<Label>) {
+        self.counts.write().await.change(&HashSet::new(), labels);
+    }
+
+    /// Replaces the details of a label - empty details are removed.
+    pub async fn describe(&self, label: Label, details: LabelInfo) -> Result<()> {
+        self.info.update(|info| {
+            if details.is_empty() {
+                info.remove(&label);
+            } else {
+                info.insert(label, details);
+            }
+        }).await;
+
+        return self.info.flush().await;
+    }
+
+    /// Writes all pending changes immediately.
+    pub async fn flush(&self) -> Result<()> {
+        return self.info.flush().await;
+    }
+
+    /// Replaces a label by another label on all archived documents or removes it if no replacement is given.
+    ///
+    /// All children of the label are moved along with it. The details of the labels are moved to the replacements
+    /// unless they have details on their own. A document which can not be changed does not stop the others from being
+    /// changed, so all changed and failed documents are returned.
+    pub async fn relabel<'r>(&self, repository: &'r Repository, from: &Label, to: Option<&Label>) -> Result<Relabeled<'r>> {
+        let replace = |label: &Label| -> Option<Option<Label>> {
+            if label != from && !label.is_descendant_of(from) {
+                return None;
+            }
+
+            return Some(to.and_then(|to| label.rebase(from, to)));
+        };
+
+        let mut counts = self.counts.write().await;
+
+        let mut relabeled = Relabeled {
+            changed: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        for bundle in repository.archive().list().await? {
+            let mut metadata = match bundle.read_metadata().await {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    relabeled.failed.push((*bundle.id(), err));
+                    continue;
+                }
+            };
+
+            if !metadata.labels.iter().chain(metadata.suggested.iter()).any(|label| replace(label).is_some()) {
+                continue;
+            }
+
+            let labels = metadata.labels.iter()
+                .filter_map(|label| replace(label).unwrap_or_else(|| Some(label.clone())))
+                .collect::<HashSet<_>>();
+
+            let previous = std::mem::replace(&mut metadata.labels, labels);
+            metadata.suggested = metadata.suggested.iter()
+                .filter_map(|label| replace(label).unwrap_or_else(|| Some(label.clone())))
+                .collect();
+
+            info!("Relabeling {} -> {:?} in bundle {}", from, to.map(Label::to_string), bundle.id());
+            if let Err(err) = bundle.write_metadata(&metadata).await {
+                relabeled.failed.push((*bundle.id(), err));
+                continue;
+            }
+
+            // Only count the labels once the document has been changed
+            counts.change(&previous, &metadata.labels);
+
+            relabeled.changed.push(bundle);
+        }
+
+        let moved = self.info.read().await.keys()
+            .filter(|label| replace(label).is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !moved.is_empty() {
+            self.info.update(|info| {
+                for label in moved {
+                    if let Some(details) = info.remove(&label) {
+                        if let Some(Some(label)) = replace(&label) {
+                            info.entry(label).or_insert(details);
+                        }
+                    }
+                }
+            }).await;
+
+            self.info.flush().await?;
+        }
+
+        return Ok(relabeled);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::meta::Metadata;
+    use crate::proto::model::{DocId, Kind};
+
+    use super::*;
+
+    async fn archive(repository: &Repository, labels: &[&str], suggested: &[&str]) -> DocId {
+        let staging = repository.stage().await.unwrap();
+
+        Metadata {
+            labels: labels.iter().map(|&label| Label::from(label)).collect(),
+            suggested: suggested.iter().map(|&label| Label::from(label)).collect(),
+            ..Metadata::new()
+        }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+        let archived = staging.create().await.unwrap()
+            .archive().await.unwrap();
+
+        return *archived.id();
+    }
+
+    async fn metadata(repository: &Repository, id: DocId) -> (HashSet<Label>, HashSet<Label>) {
+        let metadata = repository.archive().get(id).await.unwrap()
+            .read_metadata().await.unwrap();
+
+        return (metadata.labels, metadata.suggested);
+    }
+
+    fn set(labels: &[&str]) -> HashSet<Label> {
+        return labels.iter().map(|&label| Label::from(label)).collect();
+    }
+
+    #[tokio::test]
+    async fn test_counts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        archive(&repository, &["a", "b"], &[]).await;
+        archive(&repository, &["b"], &[]).await;
+
+        let labels = Labels::open(&repository).await.unwrap();
+        assert_eq!(labels.count(&Label::from("a")).await, 1);
+        assert_eq!(labels.count(&Label::from("b")).await, 2);
+        assert_eq!(labels.count(&Label::from("c")).await, 0);
+
+        labels.add(&set(&["b", "c"])).await;
+        assert_eq!(labels.count(&Label::from("b")).await, 3);
+        assert_eq!(labels.count(&Label::from("c")).await, 1);
+
+        labels.describe(Label::from("d"), LabelInfo {
+            description: Some(String::from("Unused")),
+            color: None,
+        }).await.unwrap();
+
+        let all = labels.all().await;
+        assert_eq!(all.len(), 4);
+        assert_eq!(all.get(&Label::from("d")).map(|(count, total, _)| (*count, *total)), Some((0, 0)));
+        assert!(labels.exists(&Label::from("d")).await);
+        assert!(!labels.exists(&Label::from("e")).await);
+    }
+
+    #[tokio::test]
+    async fn test_totals() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        archive(&repository, &["Finance/Bank", "Finance/Tax"], &[]).await;
+        archive(&repository, &["Finance", "Finance/Bank/Statements"], &[]).await;
+
+        let labels = Labels::open(&repository).await.unwrap();
+
+        // Documents having multiple children of a label are counted once
+        assert_eq!(labels.count(&Label::from("Finance")).await, 1);
+        assert_eq!(labels.total(&Label::from("Finance")).await, 2);
+        assert_eq!(labels.count(&Label::from("Finance/Bank")).await, 1);
+        assert_eq!(labels.total(&Label::from("Finance/Bank")).await, 2);
+        assert_eq!(labels.total(&Label::from("Finance/Bank/Statements")).await, 1);
+
+        labels.relabel(&repository, &Label::from("Finance/Bank"), Some(&Label::from("Bank"))).await.unwrap();
+        assert_eq!(labels.total(&Label::from("Finance")).await, 2);
+        assert_eq!(labels.total(&Label::from("Finance/Bank")).await, 0);
+        assert_eq!(labels.total(&Label::from("Bank")).await, 2);
+
+        let all = labels.all().await;
+        assert_eq!(all.get(&Label::from("Bank")).map(|(count, total, _)| (*count, *total)), Some((1, 2)));
+    }
+
+    #[tokio::test]
+    async fn test_relabel() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let first = archive(&repository, &["a", "b"], &["a"]).await;
+        let second = archive(&repository, &["b"], &[]).await;
+        let third = archive(&repository, &["c"], &["b"]).await;
+
+        let labels = Labels::open(&repository).await.unwrap();
+
+        let changed = labels.relabel(&repository, &Label::from("a"), Some(&Label::from("b"))).await.unwrap().changed;
+        assert_eq!(changed.iter().map(|bundle| *bundle.id()).collect::<Vec<_>>(), vec![first]);
+
+        assert_eq!(metadata(&repository, first).await, (set(&["b"]), set(&["b"])));
+        assert_eq!(metadata(&repository, second).await, (set(&["b"]), set(&[])));
+        assert_eq!(labels.count(&Label::from("a")).await, 0);
+        assert_eq!(labels.count(&Label::from("b")).await, 2);
+
+        let changed = labels.relabel(&repository, &Label::from("b"), None).await.unwrap().changed;
+        assert_eq!(changed.len(), 3);
+
+        assert_eq!(metadata(&repository, first).await, (set(&[]), set(&[])));
+        assert_eq!(metadata(&repository, third).await, (set(&["c"]), set(&[])));
+        assert_eq!(labels.count(&Label::from("b")).await, 0);
+        assert_eq!(labels.count(&Label::from("c")).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_relabel_children() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let first = archive(&repository, &["Finance/Bank/Statements", "Home"], &["Finance/Bank"]).await;
+        let second = archive(&repository, &["Finances"], &[]).await;
+
+        let labels = Labels::open(&repository).await.unwrap();
+        assert!(labels.exists(&Label::from("Finance")).await);
+        assert!(!labels.exists(&Label::from("Finance/Bank/Statements/Old")).await);
+
+        let changed = labels.relabel(&repository, &Label::from("Finance"), Some(&Label::from("Money"))).await.unwrap().changed;
+        assert_eq!(changed.len(), 1);
+
+        assert_eq!(metadata(&repository, first).await, (set(&["Money/Bank/Statements", "Home"]), set(&["Money/Bank"])));
+        assert_eq!(metadata(&repository, second).await, (set(&["Finances"]), set(&[])));
+        assert_eq!(labels.count(&Label::from("Money/Bank/Statements")).await, 1);
+        assert!(!labels.exists(&Label::from("Finance")).await);
+
+        labels.relabel(&repository, &Label::from("Money/Bank"), None).await.unwrap();
+        assert_eq!(metadata(&repository, first).await, (set(&["Home"]), set(&[])));
+    }
+
+    #[tokio::test]
+    async fn test_relabel_failed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let first = archive(&repository, &["a"], &[]).await;
+        let second = archive(&repository, &["a"], &[]).await;
+
+        let labels = Labels::open(&repository).await.unwrap();
+
+        // Break the metadata of one document
+        let broken = repository.archive().get(second).await.unwrap();
+        std::fs::write(broken.path_of(Kind::Metadata), b"broken").unwrap();
+
+        let relabeled = labels.relabel(&repository, &Label::from("a"), Some(&Label::from("b"))).await.unwrap();
+        assert_eq!(relabeled.changed.iter().map(|bundle| *bundle.id()).collect::<Vec<_>>(), vec![first]);
+        assert_eq!(relabeled.failed.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![second]);
+
+        // The other documents are changed and counted anyway
+        assert_eq!(metadata(&repository, first).await, (set(&["b"]), set(&[])));
+        assert_eq!(labels.count(&Label::from("a")).await, 1);
+        assert_eq!(labels.count(&Label::from("b")).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_info() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let details = LabelInfo {
+            description: Some(String::from("Bills to pay")),
+            color: Some(String::from("#1e90ff")),
+        };
+
+        let labels = Labels::open(&repository).await.unwrap();
+        labels.describe(Label::from("invoice"), details.clone()).await.unwrap();
+        labels.relabel(&repository, &Label::from("invoice"), Some(&Label::from("bill"))).await.unwrap();
+
+        // The details are persisted in the repository
+        let labels = Labels::open(&repository).await.unwrap();
+        assert_eq!(labels.get(&Label::from("invoice")).await, None);
+        assert_eq!(labels.get(&Label::from("bill")).await, Some(details));
+
+        labels.describe(Label::from("bill"), LabelInfo::default()).await.unwrap();
+        assert!(labels.all().await.is_empty());
+    }
+}
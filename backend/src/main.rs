@@ -1,24 +1,40 @@
 #![feature(bool_to_option)]
 #![feature(try_blocks)]
 
+use std::sync::Arc;
+
 pub use adacta_proto as proto;
 use anyhow::Result;
-use clap::{App, Arg};
+use clap::{App, Arg, SubCommand};
 
+use crate::audit::Audit;
 use crate::auth::Authenticator;
-use crate::config::{Config, Index as IndexConfig, Juicer as JuicerConfig, Suggester as SuggesterConfig};
+use crate::config::{Config, Index as IndexConfig, Juicer as JuicerConfig};
+use crate::extractor::Extractor;
 use crate::index::Index;
 use crate::juicer::Juicer;
+use crate::keys::Keys;
+use crate::labels::Labels;
 use crate::repository::Repository;
+use crate::revocations::Revocations;
+use crate::schema::Schema;
+use crate::suggester::evaluation::{DEFAULT_FOLDS, Evaluator};
 use crate::suggester::Suggester;
 
+pub mod audit;
 pub mod auth;
 pub mod config;
+pub mod extractor;
 pub mod index;
 pub mod juicer;
+pub mod keys;
+pub mod labels;
 pub mod meta;
 pub mod suggester;
 pub mod repository;
+pub mod revocations;
+pub mod schema;
+pub mod store;
 pub mod utils;
 pub mod web;
 
@@ -40,24 +56,60 @@ async fn main() -> Result<()> {
             .help("Sets a custom config file")
             .takes_value(true)
             .default_value("adacta.yaml"))
+        .subcommand(SubCommand::with_name("evaluate")
+            .about("Cross-validates the label suggestions over the archive")
+            .arg(Arg::with_name("folds")
+                .long("folds")
+                .short("f")
+                .value_name("N")
+                .help("Number of folds")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("reindex")
+            .about("Re-creates the index from the archive"))
         .get_matches();
 
 
     let config = Config::load(matches.value_of("config").expect("No config arg")).await?;
 
-    // Create auth instance
-    let auth = Authenticator::from_config(config.auth).await?;
-
     // Open repository
     let repo = Repository::from_config(config.repository).await?;
 
+    // Create evaluator for the configured suggester
+    let evaluator = Evaluator::from_config(config.suggester.clone());
+
+    if let Some(matches) = matches.subcommand_matches("evaluate") {
+        let folds = matches.value_of("folds")
+            .map(str::parse).transpose()?
+            .unwrap_or(DEFAULT_FOLDS);
+
+        return evaluate(&evaluator, &repo, folds).await;
+    }
+
+    // Load property definitions
+    let schema = Schema::from_config(config.properties)?;
+
+    if matches.subcommand_matches("reindex").is_some() {
+        let index: Box<dyn Index + Send + Sync> = match config.index {
+            IndexConfig::Elasticsearch(config) => {
+                Box::new(crate::index::elasticsearch::Index::recreate(config, schema).await?)
+            }
+        };
+
+        return crate::index::reindex(index.as_ref(), &repo).await;
+    }
+
     // Connect to index
     let index: Box<dyn Index + Send + Sync> = match config.index {
         IndexConfig::Elasticsearch(config) => {
-            Box::new(crate::index::elasticsearch::Index::from_config(config).await?)
+            Box::new(crate::index::elasticsearch::Index::from_config(config, schema.clone()).await?)
         }
     };
 
+    // Re-index the archive if the indexed documents lack fields
+    if index.requires_reindex().await {
+        crate::index::reindex(index.as_ref(), &repo).await?;
+    }
+
     // Create juicer instance
     let juicer: Box<dyn Juicer + Send + Sync> = match config.juicer {
         JuicerConfig::Docker(config) => {
@@ -65,18 +117,62 @@ async fn main() -> Result<()> {
         }
     };
 
-    // Load suggester
-    let suggester: Box<dyn Suggester + Send + Sync> = match config.suggester {
-        SuggesterConfig::Dumb(config) => {
-            Box::new(crate::suggester::dumb::Suggester::from_config(config).await?)
-        }
-        SuggesterConfig::Bayesic(config) => {
-            Box::new(crate::suggester::bayesian::Suggester::from_config(config).await?)
-        }
-    };
+    // Load suggester and keep a handle to persist its model after shutdown
+    let suggester = Arc::<dyn Suggester + Send + Sync>::from(crate::suggester::from_config(config.suggester, repo.data_path()).await?);
+
+    // Re-train the suggester if its model does not match anymore
+    if suggester.requires_training().await {
+        crate::suggester::retrain(suggester.clone(), &repo).await?;
+    }
+
+    // Create extractor instance
+    let extractor = Extractor::from_config(config.extractor)?;
+
+    // Load label details and keep a handle to persist them after shutdown
+    let labels = Arc::new(Labels::open(&repo).await?);
+
+    // Open audit log
+    let audit = Audit::open(&repo).await?;
+
+    // Create auth instance with the API keys and revoked sessions kept in the repository
+    let keys = Arc::new(Keys::open(&repo).await?);
+    let revocations = Revocations::open(&repo).await?;
+    let auth = Authenticator::from_config(config.auth, keys.clone(), revocations).await?;
 
     // Serve the HTTP Interface
-    web::server(config.web, auth, repo, index, juicer, suggester)?.launch().await?;
+    let served = match web::server(config.web, auth, repo, index, juicer, suggester.clone(), extractor, evaluator, labels.clone(), schema, audit) {
+        Ok(server) => server.launch().await.map_err(anyhow::Error::from),
+        Err(err) => Err(err),
+    };
+
+    // Pending changes are written even if the server failed
+    let suggester_flushed = suggester.flush().await;
+    let keys_flushed = keys.flush().await;
+    let labels_flushed = labels.flush().await;
+
+    return served.and(suggester_flushed).and(keys_flushed).and(labels_flushed);
+}
+
+async fn evaluate(evaluator: &Evaluator, repository: &Repository, folds: usize) -> Result<()> {
+    let evaluation = evaluator.evaluate(repository, folds).await?;
+
+    fn percent(value: Option<f64>) -> String {
+        return value.map_or_else(|| String::from("-"), |value| format!("{:.1}%", value * 100.0));
+    }
+
+    let mut labels = evaluation.labels.into_iter().collect::<Vec<_>>();
+    labels.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+
+    println!("Evaluated {} documents in {} folds", evaluation.documents, folds);
+    println!();
+    println!("{:<32} {:>10} {:>10} {:>10}", "Label", "Precision", "Recall", "Support");
+    for (label, confusion) in labels {
+        println!("{:<32} {:>10} {:>10} {:>10}",
+                 label.to_string(),
+                 percent(confusion.precision()),
+                 percent(confusion.recall()),
+                 confusion.support());
+    }
 
     return Ok(());
 }
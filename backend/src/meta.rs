@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
@@ -15,8 +15,15 @@ pub struct Metadata {
     pub title: Option<String>,
     pub pages: u32,
 
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+
     pub labels: HashSet<Label>,
 
+    /// Labels proposed by the suggester when the document was archived
+    #[serde(default)]
+    pub suggested: HashSet<Label>,
+
     pub properties: HashMap<String, String>,
 }
 
@@ -27,7 +34,9 @@ impl Metadata {
             archived: None,
             title: None,
             pages: 0,
+            date: None,
             labels: HashSet::new(),
+            suggested: HashSet::new(),
             properties: HashMap::new(),
         }
     }
@@ -59,6 +68,7 @@ impl Into<crate::proto::model::Metadata> for Metadata {
             archived: self.archived,
             title: self.title,
             pages: self.pages,
+            date: self.date,
             labels: self.labels,
             properties: self.properties,
         };
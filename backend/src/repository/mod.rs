@@ -99,6 +99,26 @@ impl<'r> Inbox<'r> {
 pub struct Archive<'r>(&'r Repository);
 
 impl<'r> Archive<'r> {
+    pub async fn list(&self) -> Result<Vec<Bundle<'r, Archived>>> {
+        let entries = match tokio::fs::read_dir(Archived::path(self.0)).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        return entries
+            .err_into::<anyhow::Error>()
+            .and_then(|entry| async move {
+                let id = DocId::from_str(entry.file_name().to_string_lossy().as_ref())?;
+                return Ok(Bundle {
+                    id,
+                    repository: self.0,
+                    state: PhantomData::default(),
+                });
+            })
+            .try_collect().await;
+    }
+
     pub async fn get(&self, id: DocId) -> Option<Bundle<'r, Archived>> {
         let bundle = Bundle {
             id,
@@ -122,6 +142,8 @@ impl Filename for Kind {
             Self::Preview => OsString::from("preview.png"),
             Self::Plaintext => OsString::from("document.txt"),
             Self::Metadata => OsString::from("metadata.json"),
+            Self::PagePreview { page } => OsString::from(format!("preview-{}.png", page)),
+            Self::Thumbnail { page } => OsString::from(format!("thumbnail-{}.png", page)),
             Self::Other { name } => OsString::from(name),
         };
     }
@@ -178,6 +200,13 @@ impl<State: BundleState> Bundle<'_, State> {
 
         return Metadata::load(file).await;
     }
+
+    pub async fn files(&self) -> Result<Vec<OsString>> {
+        return tokio::fs::read_dir(self.path()).await?
+            .map_ok(|entry| entry.file_name())
+            .err_into()
+            .try_collect().await;
+    }
 }
 
 impl Repository {
@@ -196,6 +225,9 @@ impl Repository {
 
     pub fn path(&self) -> &Path { return self.path.as_ref().as_ref(); }
 
+    /// Directory for internal state kept alongside the documents, like the suggester models.
+    pub fn data_path(&self) -> PathBuf { return self.path().join(".adacta"); }
+
     pub fn inbox(&self) -> Inbox<'_> {
         return Inbox(self);
     }
@@ -234,6 +266,19 @@ impl<'r> Bundle<'r, Inboxed> {
         return Ok(archived);
     }
 
+    pub async fn stage(&self) -> Result<Bundle<'r, Staging>> {
+        let staging = Bundle {
+            id: self.id,
+            repository: self.repository,
+            state: PhantomData::default(),
+        };
+
+        info!("Creating staged bundle {:?} to replace {:?}", staging.path(), self.path());
+        tokio::fs::create_dir_all(&staging.path()).await?;
+
+        return Ok(staging);
+    }
+
     pub async fn delete(self) -> Result<()> {
         info!("Deleting inboxed bundle {:?}", self.path());
         tokio::fs::remove_dir_all(&self.path()).await?;
@@ -257,6 +302,22 @@ impl<'r> Bundle<'r, Staging> {
         return Ok(inboxed);
     }
 
+    pub async fn replace(self, inboxed: Bundle<'r, Inboxed>) -> Result<Bundle<'r, Inboxed>> {
+        if self.id != inboxed.id {
+            return Err(anyhow!("Staged bundle {} can not replace bundle {}", self.id, inboxed.id));
+        }
+
+        // Move the replaced bundle out of the way before removing it to keep the inbox consistent
+        let replaced = self.path().with_extension("replaced");
+
+        info!("Replacing inboxed bundle {:?} with staged bundle {:?}", inboxed.path(), self.path());
+        tokio::fs::rename(&inboxed.path(), &replaced).await?;
+        tokio::fs::rename(&self.path(), &inboxed.path()).await?;
+        tokio::fs::remove_dir_all(&replaced).await?;
+
+        return Ok(inboxed);
+    }
+
     pub async fn write(&self, kind: Kind) -> Result<impl AsyncWrite> {
         let path = self.path().join(kind.filename());
 
@@ -271,6 +332,35 @@ impl<'r> Bundle<'r, Staging> {
         return Ok(file);
     }
 
+    pub async fn import<S: BundleState>(&self, source: &Bundle<'_, S>, from: Kind, to: Kind) -> Result<()> {
+        let source_path = source.path_of(&from);
+        let target_path = self.path_of(&to);
+
+        info!("Importing fragment {:?} from {:?} to {:?}", from, source_path, target_path);
+        tokio::fs::copy(source_path, target_path).await?;
+
+        return Ok(());
+    }
+
+    pub async fn remove(&self, kind: Kind) -> Result<()> {
+        let path = self.path().join(kind.filename());
+
+        info!("Removing fragment {:?} from {:?}", kind, path);
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => {
+                return Ok(());
+            }
+
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(());
+            }
+
+            Err(err) => {
+                return Err(err.into());
+            }
+        }
+    }
+
     pub async fn delete(self) -> Result<()> {
         info!("Deleting staged bundle {:?}", self.path());
         tokio::fs::remove_dir_all(&self.path()).await?;
@@ -281,19 +371,29 @@ impl<'r> Bundle<'r, Staging> {
 
 impl<'r> Bundle<'r, Inboxed> {
     pub async fn write_metadata(&self, metadata: &Metadata) -> Result<()> {
-        let path = self.path().join(Kind::Metadata.filename());
+        return write_metadata(self, metadata).await;
+    }
+}
 
-        info!("Writing metadata fragment to {:?}", path);
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(path)
-            .await?;
+impl<'r> Bundle<'r, Archived> {
+    pub async fn write_metadata(&self, metadata: &Metadata) -> Result<()> {
+        return write_metadata(self, metadata).await;
+    }
+}
 
-        metadata.save(file).await?;
+async fn write_metadata<State: BundleState>(bundle: &Bundle<'_, State>, metadata: &Metadata) -> Result<()> {
+    let path = bundle.path().join(Kind::Metadata.filename());
 
-        return Ok(());
-    }
+    info!("Writing metadata fragment to {:?}", path);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .await?;
+
+    metadata.save(file).await?;
+
+    return Ok(());
 }
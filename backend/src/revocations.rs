@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::repository::Repository;
+use crate::store;
+
+/// Name of the revocation file in the data directory of the repository.
+const FILE: &str = "revoked.json";
+
+/// Version of the revocation file format.
+const FORMAT: u32 = 1;
+
+/// The revocation file contents.
+#[derive(Debug, Deserialize, Serialize)]
+struct File<R> {
+    version: u32,
+    revoked: R,
+}
+
+/// Sessions which have been logged out before their tokens expired.
+///
+/// Each session is kept until all of its tokens have expired, stored in the data directory of the repository to survive
+/// restarts.
+pub struct Revocations {
+    path: PathBuf,
+
+    /// The expiry of the revoked sessions as seconds since the epoch per session ID
+    revoked: RwLock<HashMap<String, u64>>,
+}
+
+impl Revocations {
+    pub async fn open(repository: &Repository) -> Result<Self> {
+        let path = repository.data_path();
+        tokio::fs::create_dir_all(&path).await?;
+        let path = path.join(FILE);
+
+        let revoked = store::read(&path, Self::decode).await?
+            .unwrap_or_default();
+
+        return Ok(Self {
+            path,
+            revoked: RwLock::new(revoked),
+        });
+    }
+
+    fn decode(data: &[u8]) -> Result<HashMap<String, u64>> {
+        let file = serde_json::from_slice::<File<HashMap<String, u64>>>(data)?;
+        if file.version != FORMAT {
+            return Err(anyhow!("Unsupported revocation format {} - expected {}", file.version, FORMAT));
+        }
+
+        return Ok(file.revoked);
+    }
+
+    pub async fn is_revoked(&self, id: &str) -> bool {
+        return self.revoked.read().await.contains_key(id);
+    }
+
+    /// Revokes a session until it expires and forgets about all other expired sessions.
+    ///
+    /// Returns whether the session has not been revoked before.
+    pub async fn revoke(&self, id: &str, expiry: u64, now: u64) -> Result<bool> {
+        let mut revoked = self.revoked.write().await;
+
+        revoked.retain(|_, expiry| *expiry > now);
+        if revoked.insert(id.to_string(), expiry).is_some() {
+            return Ok(false);
+        }
+
+        let data = serde_json::to_vec_pretty(&File { version: FORMAT, revoked: &*revoked })?;
+        store::write(&self.path, &data).await?;
+
+        return Ok(true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let revocations = Revocations::open(&repository).await.unwrap();
+        assert!(!revocations.is_revoked("a").await);
+
+        assert!(revocations.revoke("a", 100, 50).await.unwrap());
+        assert!(revocations.is_revoked("a").await);
+
+        // Sessions are revoked only once
+        assert!(!revocations.revoke("a", 100, 60).await.unwrap());
+
+        // Expired sessions are removed
+        revocations.revoke("b", 300, 200).await.unwrap();
+        assert!(!revocations.is_revoked("a").await);
+
+        // The revocations are persisted in the repository
+        let revocations = Revocations::open(&repository).await.unwrap();
+        assert!(revocations.is_revoked("b").await);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Properties as Config, Property as PropertyConfig, PropertyType as Kind};
+use crate::proto::model::Label;
+
+/// A property value converted to its defined type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Date(NaiveDate),
+    Money { amount: f64, currency: String },
+}
+
+#[derive(Debug, Clone)]
+struct Definition {
+    kind: Kind,
+    required: Vec<Label>,
+}
+
+/// The definitions of all known properties.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    strict: bool,
+    definitions: HashMap<String, Definition>,
+}
+
+impl Schema {
+    pub fn from_config(config: Config) -> Result<Self> {
+        let definitions = config.definitions.into_iter()
+            .map(|(key, PropertyConfig { kind, required })| {
+                if let Kind::Enum { values } = &kind {
+                    if values.is_empty() {
+                        return Err(anyhow!("Enum property {} requires at least one value", key));
+                    }
+                }
+
+                let required = required.into_iter().map(Label::from).collect();
+
+                return Ok((key, Definition { kind, required }));
+            })
+            .collect::<Result<_>>()?;
+
+        return Ok(Self {
+            strict: config.strict,
+            definitions,
+        });
+    }
+
+    /// The type of all defined properties.
+    pub fn kinds(&self) -> impl Iterator<Item=(&str, &Kind)> {
+        return self.definitions.iter()
+            .map(|(key, definition)| (key.as_str(), &definition.kind));
+    }
+
+    /// The type of a defined property.
+    pub fn kind(&self, key: &str) -> Option<&Kind> {
+        return self.definitions.get(key)
+            .map(|definition| &definition.kind);
+    }
+
+    /// Parses a bound of a range query on a property.
+    ///
+    /// Amounts are compared regardless of their currency and are therefore bounded by plain numbers.
+    pub fn bound(&self, key: &str, value: &str) -> Result<Value> {
+        let kind = self.kind(key)
+            .ok_or_else(|| anyhow!("Unknown property: {}", key))?;
+
+        return match kind {
+            Kind::Number | Kind::Money => Ok(Value::Number(parse_number(value)?)),
+            Kind::Date => parse(kind, value),
+            _ => Err(anyhow!("Property {} can not be queried by range", key)),
+        };
+    }
+
+    /// Checks the properties of a document having the given labels.
+    pub fn validate(&self, labels: &HashSet<Label>, properties: &HashMap<String, String>) -> Result<()> {
+        for (key, value) in properties {
+            match self.definitions.get(key) {
+                Some(_) if value.is_empty() => {}
+
+                Some(definition) => {
+                    parse(&definition.kind, value)
+                        .map_err(|err| anyhow!("Invalid value for property {}: {}", key, err))?;
+                }
+
+                None if self.strict => {
+                    return Err(anyhow!("Unknown property: {}", key));
+                }
+
+                None => {}
+            }
+        }
+
+        for (key, definition) in &self.definitions {
+            let missing = properties.get(key).map_or(true, String::is_empty);
+            if !missing {
+                continue;
+            }
+
+            let requiring = definition.required.iter()
+                .find(|required| labels.iter().any(|label| label == *required || label.is_descendant_of(required)));
+
+            if let Some(requiring) = requiring {
+                return Err(anyhow!("Property {} is required for label {}", key, requiring));
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Converts all defined and valid properties to their type.
+    pub fn typed(&self, properties: &HashMap<String, String>) -> HashMap<String, Value> {
+        return properties.iter()
+            .filter_map(|(key, value)| {
+                let definition = self.definitions.get(key)?;
+                let value = parse(&definition.kind, value).ok()?;
+                return Some((key.clone(), value));
+            })
+            .collect();
+    }
+}
+
+fn parse(kind: &Kind, value: &str) -> Result<Value> {
+    return match kind {
+        Kind::String => Ok(Value::String(value.to_string())),
+
+        Kind::Number => Ok(Value::Number(parse_number(value)?)),
+
+        Kind::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Value::Date)
+            .map_err(|_| anyhow!("Not a date (YYYY-MM-DD): {}", value)),
+
+        Kind::Money => parse_money(value)
+            .ok_or_else(|| anyhow!("Not an amount with currency (like 12.34 EUR): {}", value)),
+
+        Kind::Enum { values } => {
+            if !values.iter().any(|allowed| allowed == value) {
+                return Err(anyhow!("Not one of {}: {}", values.join(", "), value));
+            }
+
+            Ok(Value::String(value.to_string()))
+        }
+    };
+}
+
+/// Parses an amount with the currency code or symbol in front of or after it.
+///
+/// The amount may use a decimal point or a decimal comma - the other one is ignored for grouping digits.
+fn parse_money(value: &str) -> Option<Value> {
+    let value = value.trim();
+
+    let start = value.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let (currency, amount) = if start == 0 {
+        let end = value.rfind(|c: char| c.is_ascii_digit())? + 1;
+        (&value[end..], &value[..end])
+    } else {
+        (&value[..start], &value[start..])
+    };
+
+    let currency = match currency.trim() {
+        "€" => "EUR",
+        currency if currency.len() == 3 && currency.chars().all(|c| c.is_ascii_uppercase()) => currency,
+        _ => return None,
+    };
+
+    let decimal = match (amount.rfind('.'), amount.rfind(',')) {
+        (Some(point), Some(comma)) => Some(if point > comma { '.' } else { ',' }),
+        (Some(_), None) if amount.matches('.').count() == 1 => Some('.'),
+        (None, Some(_)) if amount.matches(',').count() == 1 => Some(','),
+        _ => None,
+    };
+
+    let amount = amount.chars()
+        .filter_map(|c| match c {
+            '.' | ',' if Some(c) == decimal => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect::<String>();
+
+    return Some(Value::Money {
+        amount: parse_number(&amount).ok()?,
+        currency: currency.to_string(),
+    });
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    return value.parse::<f64>().ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| anyhow!("Not a number: {}", value));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::Property;
+
+    use super::*;
+
+    fn schema(strict: bool) -> Schema {
+        let mut definitions = HashMap::new();
+        definitions.insert(String::from("amount"), Property {
+            kind: Kind::Money,
+            required: vec![String::from("Finance/Invoice")],
+        });
+        definitions.insert(String::from("pages"), Property {
+            kind: Kind::Number,
+            required: vec![],
+        });
+        definitions.insert(String::from("due"), Property {
+            kind: Kind::Date,
+            required: vec![],
+        });
+        definitions.insert(String::from("state"), Property {
+            kind: Kind::Enum { values: vec![String::from("open"), String::from("paid")] },
+            required: vec![],
+        });
+
+        return Schema::from_config(Config { strict, definitions }).unwrap();
+    }
+
+    fn properties(properties: &[(&str, &str)]) -> HashMap<String, String> {
+        return properties.iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+    }
+
+    fn labels(labels: &[&str]) -> HashSet<Label> {
+        return labels.iter().map(|&label| Label::from(label)).collect();
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = schema(false);
+
+        assert!(schema.validate(&labels(&[]), &properties(&[
+            ("amount", "12.34 EUR"),
+            ("pages", "3"),
+            ("due", "2020-02-29"),
+            ("state", "paid"),
+            ("other", "anything"),
+        ])).is_ok());
+
+        assert!(schema.validate(&labels(&[]), &properties(&[("amount", "12.34")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("amount", "12.34 eur")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("amount", "EUR 12.34 EUR")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("pages", "three")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("pages", "NaN")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("due", "2020-02-30")])).is_err());
+        assert!(schema.validate(&labels(&[]), &properties(&[("state", "unknown")])).is_err());
+
+        // Empty values are allowed unless required
+        assert!(schema.validate(&labels(&[]), &properties(&[("pages", "")])).is_ok());
+    }
+
+    #[test]
+    fn test_validate_strict() {
+        let schema = schema(true);
+
+        assert!(schema.validate(&labels(&[]), &properties(&[("pages", "3")])).is_ok());
+        assert!(schema.validate(&labels(&[]), &properties(&[("page", "3")])).is_err());
+    }
+
+    #[test]
+    fn test_validate_required() {
+        let schema = schema(false);
+
+        assert!(schema.validate(&labels(&["Finance"]), &properties(&[])).is_ok());
+        assert!(schema.validate(&labels(&["Finance/Invoice"]), &properties(&[])).is_err());
+        assert!(schema.validate(&labels(&["Finance/Invoice/Phone"]), &properties(&[("amount", "")])).is_err());
+        assert!(schema.validate(&labels(&["Finance/Invoice/Phone"]), &properties(&[("amount", "5 EUR")])).is_ok());
+    }
+
+    #[test]
+    fn test_bound() {
+        let schema = schema(false);
+
+        assert_eq!(schema.bound("amount", "10").unwrap(), Value::Number(10.0));
+        assert_eq!(schema.bound("pages", "3").unwrap(), Value::Number(3.0));
+        assert_eq!(schema.bound("due", "2020-02-29").unwrap(), Value::Date(NaiveDate::from_ymd(2020, 2, 29)));
+
+        assert!(schema.bound("amount", "10 EUR").is_err());
+        assert!(schema.bound("due", "today").is_err());
+        assert!(schema.bound("state", "open").is_err());
+        assert!(schema.bound("other", "3").is_err());
+    }
+
+    #[test]
+    fn test_typed() {
+        let schema = schema(false);
+
+        let typed = schema.typed(&properties(&[
+            ("amount", "-12.5 EUR"),
+            ("pages", "3"),
+            ("due", "invalid"),
+            ("other", "anything"),
+        ]));
+
+        assert_eq!(typed.len(), 2);
+        assert_eq!(typed.get("amount"), Some(&Value::Money { amount: -12.5, currency: String::from("EUR") }));
+        assert_eq!(typed.get("pages"), Some(&Value::Number(3.0)));
+    }
+
+    #[test]
+    fn test_typed_money() {
+        let schema = schema(false);
+
+        let money = |value: &str| schema.typed(&properties(&[("amount", value)])).remove("amount");
+        let eur = |amount: f64| Some(Value::Money { amount, currency: String::from("EUR") });
+
+        assert_eq!(money("12.50 EUR"), eur(12.5));
+        assert_eq!(money("12,50 EUR"), eur(12.5));
+        assert_eq!(money("€ 12,50"), eur(12.5));
+        assert_eq!(money("€12,50"), eur(12.5));
+        assert_eq!(money("12,50€"), eur(12.5));
+        assert_eq!(money("EUR -3,10"), eur(-3.1));
+        assert_eq!(money("1.234,56 EUR"), eur(1234.56));
+        assert_eq!(money("1,234.56 EUR"), eur(1234.56));
+        assert_eq!(money("1.234.567 EUR"), eur(1234567.0));
+        assert_eq!(money("12 USD"), Some(Value::Money { amount: 12.0, currency: String::from("USD") }));
+
+        assert_eq!(money("12,50"), None);
+        assert_eq!(money("€"), None);
+        assert_eq!(money("12,50 Euro"), None);
+    }
+}
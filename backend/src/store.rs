@@ -0,0 +1,315 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, Mutex, RwLock, RwLockReadGuard};
+
+/// Time to wait after a change before writing the value, collecting all further changes in the meantime.
+const DEBOUNCE: Duration = Duration::from_secs(5);
+
+type Encoder<T> = Box<dyn Fn(&T) -> Result<Vec<u8>> + Send + Sync>;
+
+struct Inner<T> {
+    path: PathBuf,
+    value: RwLock<T>,
+    encode: Encoder<T>,
+
+    dirty: AtomicBool,
+    writing: Mutex<()>,
+}
+
+impl<T> Inner<T> {
+    async fn flush(&self) -> Result<()> {
+        let _writing = self.writing.lock().await;
+
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let result: Result<()> = async {
+            let data = (self.encode)(&*self.value.read().await)?;
+            write(&self.path, &data).await?;
+            return Ok(());
+        }.await;
+
+        if result.is_err() {
+            // Retry with the next flush
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+
+        return result;
+    }
+}
+
+/// A value kept in memory and persisted to a file, like the model of a suggester.
+///
+/// Changes are written in the background after a short delay to avoid rewriting the whole file for each change. The
+/// file is replaced atomically and the previous generation is kept as backup. Pending changes are lost if the store is
+/// dropped without flushing.
+pub struct Store<T> {
+    inner: Arc<Inner<T>>,
+    changed: mpsc::UnboundedSender<()>,
+}
+
+impl<T: Send + Sync + 'static> Store<T> {
+    pub fn new(path: PathBuf, value: T, encode: impl Fn(&T) -> Result<Vec<u8>> + Send + Sync + 'static) -> Self {
+        return Self::with_delay(path, value, DEBOUNCE, encode);
+    }
+
+    fn with_delay(path: PathBuf, value: T, delay: Duration, encode: impl Fn(&T) -> Result<Vec<u8>> + Send + Sync + 'static) -> Self {
+        let inner = Arc::new(Inner {
+            path,
+            value: RwLock::new(value),
+            encode: Box::new(encode),
+            dirty: AtomicBool::new(false),
+            writing: Mutex::new(()),
+        });
+
+        let (changed, mut changes) = mpsc::unbounded_channel();
+
+        let weak = Arc::downgrade(&inner);
+        tokio::spawn(async move {
+            while changes.recv().await.is_some() {
+                tokio::time::delay_for(delay).await;
+
+                // All changes made during the delay are covered by this write
+                while changes.try_recv().is_ok() {}
+
+                let inner = match weak.upgrade() {
+                    Some(inner) => inner,
+                    None => break,
+                };
+
+                if let Err(err) = inner.flush().await {
+                    error!("Failed to write {:?}: {}", inner.path, err);
+                }
+            }
+        });
+
+        return Self { inner, changed };
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        return self.inner.value.read().await;
+    }
+
+    /// Modifies the value and schedules writing it.
+    pub async fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut value = self.inner.value.write().await;
+        let result = f(&mut value);
+
+        self.inner.dirty.store(true, Ordering::SeqCst);
+        let _ = self.changed.send(());
+
+        return result;
+    }
+
+    /// Writes all pending changes immediately.
+    pub async fn flush(&self) -> Result<()> {
+        return self.inner.flush().await;
+    }
+}
+
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".");
+    name.push(extension);
+
+    return path.with_file_name(name);
+}
+
+/// Reads and decodes a file, falling back to its backup if the file is missing or can not be decoded.
+///
+/// Returns `None` if neither the file nor its backup exist.
+pub async fn read<T>(path: impl AsRef<Path>, decode: impl Fn(&[u8]) -> Result<T>) -> Result<Option<T>> {
+    let path = path.as_ref();
+    let backup = sibling(path, "bak");
+
+    let mut found = false;
+    for candidate in &[path, backup.as_path()] {
+        let data = match tokio::fs::read(candidate).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        found = true;
+
+        match decode(&data) {
+            Ok(value) => {
+                if *candidate != path {
+                    info!("Restored {:?} from backup {:?}", path, candidate);
+                }
+
+                return Ok(Some(value));
+            }
+            Err(err) => {
+                warn!("File {:?} can not be decoded: {}", candidate, err);
+            }
+        }
+    }
+
+    if found {
+        return Err(anyhow!("File {:?} and its backup can not be decoded", path));
+    }
+
+    return Ok(None);
+}
+
+/// Replaces a file atomically while keeping the previous generation as backup.
+pub async fn write(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let temp = sibling(path, "tmp");
+    let backup = sibling(path, "bak");
+
+    let mut file = tokio::fs::File::create(&temp).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    // Link the current generation as backup so that the file never goes missing
+    match tokio::fs::remove_file(&backup).await {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    match tokio::fs::hard_link(path, &backup).await {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    tokio::fs::rename(&temp, path).await?;
+
+    // Make the renaming durable
+    if let Some(parent) = path.parent() {
+        tokio::fs::File::open(parent).await?.sync_all().await?;
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Result<String> {
+        let value = String::from_utf8(data.to_vec())?;
+        if value == "corrupted" {
+            return Err(anyhow!("Corrupted"));
+        }
+
+        return Ok(value);
+    }
+
+    #[tokio::test]
+    async fn test_write() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("model.json");
+
+        assert_eq!(read(&path, decode).await.unwrap(), None);
+
+        write(&path, b"first").await.unwrap();
+        write(&path, b"second").await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read(tmp.path().join("model.json.bak")).unwrap(), b"first");
+        assert!(!tmp.path().join("model.json.tmp").exists());
+
+        assert_eq!(read(&path, decode).await.unwrap(), Some(String::from("second")));
+    }
+
+    #[tokio::test]
+    async fn test_read_backup() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("model.json");
+
+        write(&path, b"first").await.unwrap();
+        write(&path, b"second").await.unwrap();
+
+        std::fs::write(&path, b"corrupted").unwrap();
+        assert_eq!(read(&path, decode).await.unwrap(), Some(String::from("first")));
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read(&path, decode).await.unwrap(), Some(String::from("first")));
+
+        std::fs::write(tmp.path().join("model.json.bak"), b"corrupted").unwrap();
+        assert!(read(&path, decode).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("model.json");
+
+        let store = Store::new(path.clone(), String::from("initial"), |value: &String| Ok(value.as_bytes().to_vec()));
+
+        // Nothing is written without changes
+        store.flush().await.unwrap();
+        assert!(!path.exists());
+
+        store.update(|value| value.push_str(" changed")).await;
+        assert_eq!(*store.read().await, "initial changed");
+
+        store.flush().await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"initial changed");
+    }
+
+    #[tokio::test]
+    async fn test_store_debounced() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("model.json");
+
+        let delay = Duration::from_millis(100);
+        let store = Store::with_delay(path.clone(), 0u32, delay, |value: &u32| Ok(value.to_string().into_bytes()));
+
+        for _ in 0..10 {
+            store.update(|value| *value += 1).await;
+        }
+        assert!(!path.exists());
+
+        tokio::time::delay_for(delay * 5).await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"10");
+        assert!(!tmp.path().join("model.json.bak").exists());
+    }
+
+    #[tokio::test]
+    async fn test_store_burst() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("model.json");
+
+        let writes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let delay = Duration::from_millis(200);
+        let store = Store::with_delay(path.clone(), 0u32, delay, {
+            let writes = writes.clone();
+            move |value: &u32| {
+                writes.fetch_add(1, Ordering::SeqCst);
+                return Ok(value.to_string().into_bytes());
+            }
+        });
+
+        for _ in 0..1000 {
+            store.update(|value| *value += 1).await;
+        }
+
+        tokio::time::delay_for(delay * 11 / 2).await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"1000");
+        assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+        // Later changes are debounced again instead of being written by changes left over from the burst
+        store.update(|value| *value += 1).await;
+
+        tokio::time::delay_for(delay * 3 / 4).await;
+        assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+        tokio::time::delay_for(delay).await;
+        assert_eq!(std::fs::read(&path).unwrap(), b"1001");
+        assert_eq!(writes.load(Ordering::SeqCst), 2);
+    }
+}
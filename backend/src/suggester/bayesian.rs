@@ -1,217 +1,244 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
-use std::iter::Sum;
-use std::ops::{Add, AddAssign, Mul};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
 
 use crate::config::BayesicSuggester as Config;
-use crate::proto::model::Label;
+use crate::proto::model::{Label, Suggestion};
+use crate::store::{self, Store};
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Deserialize, Serialize)]
-struct Counter {
-    pub pro: u64,
-    pub con: u64,
-}
+use super::pipeline::{Fingerprint, Pipeline};
 
-impl Counter {
-    pub fn with(pro: u64, con: u64) -> Self { Self { pro, con } }
+/// Name of the model file in the model directory.
+const MODEL: &str = "bayesian.json";
 
-    pub fn neutral() -> Self { Self::with(0, 0) }
+/// Version of the model file format.
+///
+/// Models stored in another format are discarded and must be re-trained.
+///
+/// Version 2 counts the documents for the parents of all labels.
+const FORMAT: u32 = 2;
 
-    pub fn pro() -> Self { Self::with(1, 0) }
+/// The model file contents.
+#[derive(Debug, Deserialize, Serialize)]
+struct File<M> {
+    version: u32,
+    pipeline: Fingerprint,
 
-    pub fn con() -> Self { Self::with(0, 1) }
+    #[serde(flatten)]
+    model: M,
 }
 
-impl Add<&Counter> for Counter {
-    type Output = Counter;
+/// Token counts over a set of documents.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct Statistics {
+    documents: u64,
+    tokens: HashMap<String, u64>,
 
-    fn add(self, rhs: &Counter) -> Self::Output {
-        Self {
-            pro: self.pro + rhs.pro,
-            con: self.con + rhs.con,
-        }
-    }
+    /// Sum of all token counts
+    total: u64,
 }
 
-impl AddAssign<Counter> for Counter {
-    fn add_assign(&mut self, rhs: Counter) {
-        self.pro += rhs.pro;
-        self.con += rhs.con;
-    }
-}
-
-impl Mul<u64> for Counter {
-    type Output = Counter;
+impl Statistics {
+    pub fn add(&mut self, tokens: &HashMap<String, u64>) {
+        self.documents += 1;
 
-    fn mul(self, rhs: u64) -> Self::Output {
-        Self {
-            pro: self.pro * rhs,
-            con: self.con * rhs,
+        for (token, &count) in tokens {
+            // TODO: Avoid cloning if not required
+            *self.tokens.entry(token.to_string()).or_default() += count;
+            self.total += count;
         }
     }
-}
 
-impl<'a> Sum<&'a Counter> for Counter {
-    fn sum<I: Iterator<Item=&'a Counter>>(iter: I) -> Self {
-        iter.fold(Counter::neutral(), |acc, v| acc + v)
+    pub fn count(&self, token: &str) -> u64 {
+        return self.tokens.get(token).copied().unwrap_or(0);
     }
 }
 
-impl Default for Counter {
-    fn default() -> Self { Self::neutral() }
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-struct Classifier {
-    data: HashMap<String, Counter>,
+/// A multinomial naive bayes classifier deciding for each label whether a document has this label or not.
+///
+/// The statistics of the documents not having a label are derived from the statistics of all documents. Therefore
+/// each document is trained exactly once and labels added later on are classified against all documents seen before.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct Model {
+    all: Statistics,
+    labels: HashMap<Label, Statistics>,
 }
 
-impl Classifier {
-    pub fn new() -> Self {
-        Self { data: HashMap::new() }
-    }
-
-    pub fn train_pro(&mut self, tokens: &HashMap<String, u64>) {
-        self.train(tokens, Counter::pro());
-    }
+impl Model {
+    pub fn train(&mut self, tokens: &HashMap<String, u64>, labels: &HashSet<Label>) {
+        self.all.add(tokens);
 
-    pub fn train_con(&mut self, tokens: &HashMap<String, u64>) {
-        self.train(tokens, Counter::con());
+        for label in labels {
+            self.labels.entry(label.clone()).or_default().add(tokens);
+        }
     }
 
-    fn train(&mut self, tokens: &HashMap<String, u64>, counter: Counter) {
-        for (token, &count) in tokens {
-            // TODO: Avoid cloning if not required
-            *self.data.entry(token.to_string()).or_default() += counter * count;
-        }
+    /// Calculates the probability of a document having a label.
+    ///
+    /// The model file may be edited or restored from a backup - inconsistent statistics must not panic and are treated as
+    /// if no other documents were seen.
+    pub fn classify(&self, label: &Statistics, tokens: &HashMap<String, u64>) -> f64 {
+        let vocabulary = self.all.tokens.len() as f64;
 
-        println!("{:?}", self.data);
-    }
+        // Class priors with laplace smoothing
+        let documents = self.all.documents as f64 + 2.0;
+        let mut pro = ((label.documents as f64 + 1.0) / documents).ln();
+        let mut con = ((self.all.documents.saturating_sub(label.documents) as f64 + 1.0) / documents).ln();
 
-    pub fn classify(&self, tokens: &HashMap<String, u64>) -> f64 {
-        // TODO: Cache this
-        let tally = self.data.values().sum::<Counter>();
+        let pro_total = label.total as f64 + vocabulary;
+        let con_total = self.all.total.saturating_sub(label.total) as f64 + vocabulary;
 
-        let mut pro = 0.0;
-        let mut con = 0.0;
         for (token, &count) in tokens {
-            if let Some(counter) = self.data.get(token) {
-                if counter.pro != 0 {
-                    pro += count as f64 * (counter.pro as f64 / tally.pro as f64).ln();
-                }
+            let all = self.all.count(token);
+            if all == 0 {
+                // Tokens never seen during training do not tell anything about the labels
+                continue;
+            }
 
-                if counter.con != 0 {
-                    con += count as f64 * (counter.con as f64 / tally.con as f64).ln();
-                }
-            };
-        }
+            let label = label.count(token);
 
-        let total = pro + con;
-        if total == 0.0 {
-            return 0.0;
+            pro += count as f64 * ((label as f64 + 1.0) / pro_total).ln();
+            con += count as f64 * ((all.saturating_sub(label) as f64 + 1.0) / con_total).ln();
         }
 
-        pro / total
+        // Normalize both log-likelihoods to a probability
+        return 1.0 / (1.0 + (con - pro).exp());
     }
 }
 
 pub struct Suggester {
-    path: PathBuf,
     certainty: f64,
 
-    classifiers: RwLock<HashMap<Label, Classifier>>,
+    pipeline: Pipeline,
+    outdated: bool,
+
+    model: Store<Model>,
 }
 
 impl Suggester {
-    pub async fn from_config(config: Config) -> Result<Self> {
-        let path = PathBuf::from(config.path);
+    pub async fn from_config(config: Config, path: impl AsRef<Path>) -> Result<Self> {
+        tokio::fs::create_dir_all(&path).await?;
+        let path = path.as_ref().join(MODEL);
+
+        let pipeline = Pipeline::new(config.pipeline);
+        let fingerprint = pipeline.fingerprint();
+
+        // Models in another format or trained with another pipeline are discarded and must be re-trained
+        let model = match store::read(&path, |data| Ok(serde_json::from_slice::<File<Model>>(data)?)).await {
+            Ok(Some(file)) => Self::check(&path, file, &fingerprint),
+            Ok(None) => {
+                // Models of previous versions count tokens per label and can not be converted
+                match config.path {
+                    Some(legacy) => info!("Model {:?} of a previous version is re-trained", legacy),
+                    None => info!("Model {:?} does not exist and is trained from the archive", path),
+                }
+                None
+            }
+            Err(err) => {
+                warn!("{}", err);
+                None
+            }
+        };
 
-        let classifiers = Self::load(&path).await?;
+        let outdated = model.is_none();
+        let model = Store::new(path, model.unwrap_or_default(), move |model| Self::encode(&fingerprint, model));
 
         Ok(Self {
-            path,
             certainty: config.certainty,
-            classifiers: RwLock::new(classifiers),
+            pipeline,
+            outdated,
+            model,
         })
     }
 
-    async fn load(path: impl AsRef<Path>) -> Result<HashMap<Label, Classifier>> {
-        match tokio::fs::read(path).await {
-            Ok(data) => Ok(bincode::deserialize(&data)?),
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
-            Err(err) => Err(err.into()),
+    /// Checks the model file to match the format and the given pipeline.
+    fn check(path: &Path, file: File<Model>, fingerprint: &Fingerprint) -> Option<Model> {
+        if file.version != FORMAT {
+            warn!("Model {:?} has format {} - expected {}", path, file.version, FORMAT);
+            return None;
         }
-    }
 
-    async fn save(path: impl AsRef<Path>, classifiers: &HashMap<Label, Classifier>) -> Result<()> {
-        let data = bincode::serialize(classifiers)?;
-        tokio::fs::write(path, &data).await?;
+        if &file.pipeline != fingerprint {
+            warn!("Model {:?} trained with pipeline {:?} - expected {:?}", path, file.pipeline, fingerprint);
+            return None;
+        }
 
-        Ok(())
+        return Some(file.model);
     }
 
-    fn tokenize(text: &str) -> HashMap<String, u64> {
-        let stream = text
-            .split_whitespace()
-            .map(|s| s.replace(|c: char| c.is_ascii_punctuation(), ""))
-            .map(|s| s.to_lowercase());
+    fn encode(fingerprint: &Fingerprint, model: &Model) -> Result<Vec<u8>> {
+        return Ok(serde_json::to_vec_pretty(&File {
+            version: FORMAT,
+            pipeline: fingerprint.clone(),
+            model,
+        })?);
+    }
 
-        let mut tokens = HashMap::new();
-        for token in stream {
-            *tokens.entry(token).or_insert(0) += 1;
-        }
+    async fn suggest(&self, tokens: &HashMap<String, u64>, certainty: f64) -> Vec<Suggestion> {
+        let model = self.model.read().await;
 
-        tokens
-    }
+        let mut suggestions = model.labels.iter()
+            .map(|(label, statistics)| Suggestion {
+                label: label.clone(),
+                score: model.classify(statistics, tokens),
+            })
+            .filter(|suggestion| suggestion.score >= certainty)
+            .collect::<Vec<_>>();
+
+        suggestions = super::most_specific(suggestions);
 
-    async fn suggest(&self, tokens: &HashMap<String, u64>) -> HashSet<Label> {
-        let classifiers = self.classifiers.read().await;
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+            .then_with(|| a.label.to_string().cmp(&b.label.to_string())));
 
-        return classifiers.iter()
-            .filter_map(|(label, classifier)| (classifier.classify(&tokens) >= self.certainty).then(|| label.clone()))
-            .collect();
+        return suggestions;
     }
 }
 
 #[async_trait]
 impl super::Suggester for Suggester {
     async fn labels(&self) -> HashSet<Label> {
-        let classifiers = self.classifiers.read().await;
+        let model = self.model.read().await;
 
-        return classifiers.keys().cloned().collect();
+        return model.labels.keys().cloned().collect();
     }
 
-    async fn guess(&self, text: &str) -> Result<HashSet<Label>> {
-        let tokens = Self::tokenize(text);
+    async fn requires_training(&self) -> bool {
+        return self.outdated;
+    }
 
-        return Ok(self.suggest(&tokens).await);
+    async fn flush(&self) -> Result<()> {
+        return self.model.flush().await;
+    }
+
+    async fn guess(&self, text: &str, certainty: Option<f64>) -> Result<Vec<Suggestion>> {
+        let tokens = self.pipeline.tokenize(text);
+
+        return Ok(self.suggest(&tokens, certainty.unwrap_or(self.certainty)).await);
     }
 
     async fn train(&self, text: &str, expected_labels: &HashSet<Label>) -> Result<()> {
-        let tokens = Self::tokenize(text);
+        let tokens = self.pipeline.tokenize(text);
 
-        // Re-calculate which labels have been proposed before
-        let proposed_labels = self.suggest(&tokens).await;
+        // Train the parents of all labels, too, to allow suggestions on every level of the hierarchy
+        let labels = super::with_ancestors(expected_labels);
 
-        let mut classifiers = self.classifiers.write().await;
+        self.model.update(|model| model.train(&tokens, &labels)).await;
 
-        // Calculate added and removed labels and train classifiers accordingly
-        for label in proposed_labels.difference(&expected_labels) {
-            let classifier = classifiers.entry(label.clone()).or_insert_with(Classifier::new);
-            classifier.train_con(&tokens);
-        }
+        Ok(())
+    }
 
-        for label in expected_labels.difference(&proposed_labels) {
-            let classifier = classifiers.entry(label.clone()).or_insert_with(Classifier::new);
-            classifier.train_pro(&tokens);
+    async fn rebuild(&self, documents: &[(String, HashSet<Label>)]) -> Result<()> {
+        let mut model = Model::default();
+        for (text, labels) in documents {
+            model.train(&self.pipeline.tokenize(text), &super::with_ancestors(labels));
         }
 
-        Self::save(&self.path, &classifiers).await?;
+        self.model.update(|current| *current = model).await;
 
         Ok(())
     }
@@ -219,10 +246,31 @@ impl super::Suggester for Suggester {
 
 #[cfg(test)]
 mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+
+    use crate::config::Pipeline as PipelineConfig;
     use crate::suggester::Suggester as _;
 
     use super::*;
 
+    fn config() -> Config {
+        return Config {
+            path: None,
+            certainty: 0.5,
+            pipeline: PipelineConfig {
+                languages: vec![],
+                stemming: false,
+                ..PipelineConfig::default()
+            },
+        };
+    }
+
+    async fn suggester(path: &Path) -> Suggester {
+        return Suggester::from_config(config(), path).await.unwrap();
+    }
+
     #[tokio::test]
     async fn classify() {
         let tmp = tempfile::tempdir().unwrap();
@@ -236,77 +284,204 @@ mod tests {
             "pea horseradish azuki bean lettuce avocado asparagus okra. kohlrabi radish okra azuki bean corn fava bean mustard tigernut jicama green bean celtuce collard greens avocado quandong fennel gumbo black-eyed pea. grape silver beet watercress potato tigernut corn groundnut. chickweed okra pea winter purslane coriander yarrow sweet pepper radish garlic brussels sprout groundnut summer purslane earthnut pea tomato spring onion azuki bean gourd. gumbo kakadu plum komatsuna black-eyed pea green bean zucchini gourd winter purslane silver beet rock melon radish asparagus spinach.",
         ];
 
+        let suggester = suggester(tmp.path()).await;
+
+        suggester.train(meat[0], &vec![Label::from("Meat"), Label::from("Foo")].into_iter().collect()).await.unwrap();
+        suggester.train(meat[1], &vec![Label::from("Meat"), Label::from("Bar")].into_iter().collect()).await.unwrap();
+        suggester.train(vegg[0], &vec![Label::from("Vegg"), Label::from("Foo")].into_iter().collect()).await.unwrap();
+        suggester.train(vegg[1], &vec![Label::from("Vegg"), Label::from("Bar")].into_iter().collect()).await.unwrap();
+
+        {
+            let model = suggester.model.read().await;
+            assert_eq!(model.all.documents, 4);
+            assert_eq!(model.labels.get("Meat").unwrap().documents, 2);
+            assert_eq!(model.labels.get("Meat").unwrap().count("landjaeger"), 6);
+            assert_eq!(model.labels.get("Vegg").unwrap().count("landjaeger"), 0);
+            assert_eq!(model.all.total, model.all.tokens.values().sum::<u64>());
+        }
+
+        let suggestions = suggester.guess("salami pancetta beef ribs", None).await.unwrap();
+        assert_eq!(suggestions.first().unwrap().label, Label::from("Meat"));
+        assert!(suggestions.iter().all(|suggestion| suggestion.label != Label::from("Vegg")));
+
+        let suggestions = suggester.guess("okra radish with some garlic", None).await.unwrap();
+        assert_eq!(suggestions.first().unwrap().label, Label::from("Vegg"));
+        assert!(suggestions.iter().all(|suggestion| suggestion.label != Label::from("Meat")));
+
+        // Unknown tokens fall back to the priors
+        let suggestions = suggester.guess("unknown", Some(0.0)).await.unwrap();
+        assert_eq!(suggestions.len(), 4);
+        assert!(suggestions.iter().all(|suggestion| (suggestion.score - 0.5).abs() < 1e-9));
+
+        let suggestions = suggester.guess("salami pancetta beef ribs", Some(0.0)).await.unwrap();
+        assert!(suggestions.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(suggestions.iter().all(|suggestion| (0.0..=1.0).contains(&suggestion.score)));
+    }
+
+    #[tokio::test]
+    async fn hierarchy() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let suggester = suggester(tmp.path()).await;
+
+        for _ in 0..3 {
+            suggester.train("insurance policy premium vehicle engine garage", &vec![Label::from("Home/Insurance/Car")].into_iter().collect()).await.unwrap();
+            suggester.train("insurance policy premium building roof garden", &vec![Label::from("Home/Insurance/House")].into_iter().collect()).await.unwrap();
+            suggester.train("statement account transfer balance", &vec![Label::from("Finance/Bank")].into_iter().collect()).await.unwrap();
+        }
+
+        {
+            let model = suggester.model.read().await;
+            assert_eq!(model.labels.get("Home").unwrap().documents, 6);
+            assert_eq!(model.labels.get("Home/Insurance").unwrap().documents, 6);
+            assert_eq!(model.labels.get("Home/Insurance/Car").unwrap().documents, 3);
+        }
+
+        // The most specific label is suggested without its parents
+        let suggestions = suggester.guess("insurance premium vehicle engine", None).await.unwrap();
+        assert_eq!(suggestions.iter().map(|suggestion| suggestion.label.clone()).collect::<Vec<_>>(),
+                   vec![Label::from("Home/Insurance/Car")]);
+
+        // Parents are scored, too, but only suggested if none of their children is
+        let suggestions = suggester.guess("insurance policy premium", Some(0.0)).await.unwrap();
+        assert!(suggestions.iter().all(|suggestion| suggestion.label != Label::from("Home/Insurance")));
+
+        let model = suggester.model.read().await;
+        let tokens = suggester.pipeline.tokenize("insurance policy premium");
+        assert!(model.classify(model.labels.get("Home/Insurance").unwrap(), &tokens) > 0.5);
+    }
+
+    #[test]
+    fn inconsistent() {
+        let mut model = Model::default();
+        model.train(&vec![(String::from("sirloin"), 1)].into_iter().collect(), &HashSet::new());
+
+        // A label claiming more documents and tokens than seen overall
+        let mut label = Statistics::default();
+        for _ in 0..3 {
+            label.add(&vec![(String::from("sirloin"), 5)].into_iter().collect());
+        }
+
+        let score = model.classify(&label, &vec![(String::from("sirloin"), 1)].into_iter().collect());
+        assert!((0.0..=1.0).contains(&score));
+    }
+
+    #[tokio::test]
+    async fn rebuild() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let suggester = suggester(tmp.path()).await;
+
+        suggester.train("sirloin meatloaf", &vec![Label::from("Meat")].into_iter().collect()).await.unwrap();
+
+        suggester.rebuild(&[
+            (String::from("okra radish"), vec![Label::from("Food/Vegg")].into_iter().collect()),
+            (String::from("okra garlic"), vec![Label::from("Food/Vegg")].into_iter().collect()),
+        ]).await.unwrap();
+
+        assert_eq!(suggester.labels().await, vec![Label::from("Food"), Label::from("Food/Vegg")].into_iter().collect());
+
+        let model = suggester.model.read().await;
+        assert_eq!(model.all.documents, 2);
+        assert_eq!(model.all.count("sirloin"), 0);
+        assert_eq!(model.labels.get("Food/Vegg").unwrap().count("okra"), 2);
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        // A missing model is trained from the documents already archived
+        let suggester = suggester(tmp.path()).await;
+        assert!(suggester.requires_training().await);
+
+        suggester.train("sirloin meatloaf", &vec![Label::from("Meat")].into_iter().collect()).await.unwrap();
+        suggester.flush().await.unwrap();
+
+        let suggester = self::suggester(tmp.path()).await;
+        assert!(!suggester.requires_training().await);
+        assert_eq!(suggester.labels().await, vec![Label::from("Meat")].into_iter().collect());
+
+        // The model is stored as readable JSON
+        let file = serde_json::from_slice::<serde_json::Value>(&std::fs::read(tmp.path().join(MODEL)).unwrap()).unwrap();
+        assert_eq!(file["version"], serde_json::json!(FORMAT));
+        assert_eq!(file["labels"]["Meat"]["tokens"]["sirloin"], serde_json::json!(1));
+
+        // A model built by another pipeline must be re-trained
+        let suggester = Suggester::from_config(Config {
+            pipeline: PipelineConfig::default(),
+            ..config()
+        }, tmp.path()).await.unwrap();
+        assert!(suggester.requires_training().await);
+        assert!(suggester.labels().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn legacy() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let legacy = tmp.path().join("legacy");
+        std::fs::write(&legacy, b"model of a previous version").unwrap();
+
+        // Models of previous versions are not migrated but re-trained
         let suggester = Suggester::from_config(Config {
-            path: tmp.path().join("bayesian").display().to_string(),
-            certainty: 0.1,
-        }).await.unwrap();
-
-        suggester.train(
-            &meat[0],
-            &vec![Label::from("Meat"), Label::from("Foo")].into_iter().collect(),
-        ).await.unwrap();
-
-        assert_eq!(suggester.classifiers.read().await.get("Meat").unwrap()
-                       .data.get("sirloin").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Foo").unwrap()
-                       .data.get("sirloin").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Meat").unwrap()
-                       .data.get("prosciutto").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Foo").unwrap()
-                       .data.get("prosciutto").unwrap(),
-                   &Counter::with(1, 0));
-
-        suggester.train(
-            &meat[1],
-            &vec![Label::from("Meat"), Label::from("Bar")].into_iter().collect(),
-        ).await.unwrap();
-
-        assert_eq!(suggester.classifiers.read().await.get("Meat").unwrap()
-                       .data.get("sirloin").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Foo").unwrap()
-                       .data.get("sirloin").unwrap(),
-                   &Counter::with(1, 1));
-        assert_eq!(suggester.classifiers.read().await.get("Bar").unwrap()
-                       .data.get("sirloin").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Meat").unwrap()
-                       .data.get("prosciutto").unwrap(),
-                   &Counter::with(1, 0));
-        assert_eq!(suggester.classifiers.read().await.get("Foo").unwrap()
-                       .data.get("prosciutto").unwrap(),
-                   &Counter::with(1, 0));
-        // assert_eq!(suggester.classifiers.read().await.get("Meat").unwrap()
-        //                .data.get("bresaola").unwrap(),
-        //            &Counter::with(1, 0));
-        // assert_eq!(suggester.classifiers.read().await.get("Foo").unwrap()
-        //                .data.get("bresaola").unwrap(),
-        //            &Counter::with(0, 1));
-        // assert_eq!(suggester.classifiers.read().await.get("Bar").unwrap()
-        //                .data.get("bresaola").unwrap(),
-        //            &Counter::with(1, 0));
-
-        suggester.train(
-            &vegg[0],
-            &vec!["Vegg".into(), "Foo".into()].into_iter().collect(),
-        ).await.unwrap();
-
-        suggester.train(
-            &vegg[1],
-            &vec![Label::from("Vegg"), Label::from("Bar")].into_iter().collect(),
-        ).await.unwrap();
-
-        assert!(suggester.classifiers.read().await.get("Meat").unwrap()
-            .classify(&Suggester::tokenize("salami")).abs() < f64::EPSILON);
-        assert!(suggester.classifiers.read().await.get("Foo").unwrap()
-            .classify(&Suggester::tokenize("salami")).abs() < f64::EPSILON);
-        assert!((suggester.classifiers.read().await.get("Bar").unwrap()
-            .classify(&Suggester::tokenize("salami")) - 1.0f64).abs() < f64::EPSILON);
-
-        assert_eq!(suggester.guess("salami pancetta beef ribs").await.unwrap(),
-                   vec![Label::from("Meat"), Label::from("Foo"), Label::from("Bar")].into_iter().collect());
+            path: Some(legacy.display().to_string()),
+            ..config()
+        }, tmp.path().join("model")).await.unwrap();
+        assert!(suggester.requires_training().await);
+        assert!(suggester.labels().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn accuracy() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+
+        // Each topic has some distinctive words and all topics share a common vocabulary
+        let common = [
+            "please", "find", "attached", "regards", "dear", "customer", "number", "date", "total", "page",
+            "thanks", "address", "office", "service", "account", "period", "amount", "reference", "contact", "information",
+        ];
+        let topics = [
+            ("Insurance", ["policy", "premium", "coverage", "claim", "insured", "damage", "liability", "deductible"]),
+            ("Tax", ["taxes", "income", "refund", "assessment", "deduction", "revenue", "fiscal", "return"]),
+            ("Energy", ["electricity", "meter", "kilowatt", "consumption", "tariff", "grid", "supply", "reading"]),
+            ("Phone", ["mobile", "minutes", "roaming", "contract", "data", "flatrate", "sim", "calls"]),
+        ];
+
+        let mut documents = (0..200)
+            .map(|_| {
+                let (label, words) = topics.choose(&mut rng).unwrap();
+                let text = (0..40)
+                    .map(|_| if rng.gen_bool(0.25) {
+                        *words.choose(&mut rng).unwrap()
+                    } else {
+                        *common.choose(&mut rng).unwrap()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                return (Label::from(*label), text);
+            })
+            .collect::<Vec<_>>();
+
+        // Train on the first part and hold back the rest for evaluation
+        let held_out = documents.split_off(150);
+
+        let suggester = suggester(tmp.path()).await;
+        for (label, text) in &documents {
+            suggester.train(text, &vec![label.clone()].into_iter().collect()).await.unwrap();
+        }
+
+        let mut correct = 0;
+        for (label, text) in &held_out {
+            let suggestions = suggester.guess(text, Some(0.0)).await.unwrap();
+            if suggestions.first().map(|suggestion| &suggestion.label) == Some(label) {
+                correct += 1;
+            }
+        }
+
+        let accuracy = correct as f64 / held_out.len() as f64;
+        assert!(accuracy >= 0.9, "Accuracy too low: {}", accuracy);
     }
 }
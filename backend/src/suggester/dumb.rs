@@ -1,40 +1,75 @@
 use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use log::info;
+use serde::{Deserialize, Serialize};
 
 use crate::config::DumbSuggester as Config;
-use crate::proto::model::Label;
+use crate::proto::model::{Label, Suggestion};
+use crate::store::{self, Store};
+
+/// Name of the model file in the model directory.
+const MODEL: &str = "dumb.json";
+
+/// Version of the model file format.
+const FORMAT: u32 = 1;
+
+/// The model file contents.
+#[derive(Debug, Deserialize, Serialize)]
+struct File<L> {
+    version: u32,
+    labels: L,
+}
 
 pub struct Suggester {
-    path: PathBuf,
-    labels: RwLock<HashSet<Label>>,
+    labels: Store<HashSet<Label>>,
 }
 
 impl Suggester {
-    pub async fn from_config(config: Config) -> Result<Self> {
-        let path = PathBuf::from(config.path);
+    pub async fn from_config(config: Config, path: impl AsRef<Path>) -> Result<Self> {
+        tokio::fs::create_dir_all(&path).await?;
+        let path = path.as_ref().join(MODEL);
 
-        let labels = RwLock::new(Self::load(&path).await?);
+        let labels = match store::read(&path, Self::decode).await? {
+            Some(labels) => labels,
+            None => match config.path {
+                Some(legacy) => {
+                    let labels = Self::migrate(legacy).await?;
+                    store::write(&path, &Self::encode(&labels)?).await?;
+                    labels
+                }
+                None => HashSet::new(),
+            },
+        };
 
-        Ok(Self { path, labels })
+        Ok(Self { labels: Store::new(path, labels, Self::encode) })
     }
 
-    async fn load(path: impl AsRef<Path>) -> Result<HashSet<Label>> {
-        match tokio::fs::read(path).await {
-            Ok(data) => Ok(bincode::deserialize(&data)?),
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(HashSet::new()),
-            Err(err) => Err(err.into()),
+    fn decode(data: &[u8]) -> Result<HashSet<Label>> {
+        let file = serde_json::from_slice::<File<HashSet<Label>>>(data)?;
+        if file.version != FORMAT {
+            return Err(anyhow!("Unsupported model format {} - expected {}", file.version, FORMAT));
         }
+
+        return Ok(file.labels);
     }
 
-    async fn save(path: impl AsRef<Path>, classifiers: &HashSet<Label>) -> Result<()> {
-        let data = bincode::serialize(classifiers)?;
-        tokio::fs::write(path, &data).await?;
+    fn encode(labels: &HashSet<Label>) -> Result<Vec<u8>> {
+        return Ok(serde_json::to_vec_pretty(&File { version: FORMAT, labels })?);
+    }
 
-        Ok(())
+    /// Migrates a bincode model written by previous versions.
+    async fn migrate(path: impl AsRef<Path>) -> Result<HashSet<Label>> {
+        match tokio::fs::read(&path).await {
+            Ok(data) => {
+                info!("Migrating model from {:?}", path.as_ref());
+                Ok(bincode::deserialize(&data)?)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(HashSet::new()),
+            Err(err) => Err(err.into()),
+        }
     }
 }
 
@@ -45,15 +80,57 @@ impl super::Suggester for Suggester {
         return labels.clone();
     }
 
-    async fn guess(&self, _text: &str) -> Result<HashSet<Label>> { Ok(HashSet::new()) }
+    async fn requires_training(&self) -> bool { false }
+
+    async fn guess(&self, _text: &str, _certainty: Option<f64>) -> Result<Vec<Suggestion>> { Ok(Vec::new()) }
 
     async fn train(&self, _text: &str, expected_labels: &HashSet<Label>) -> Result<()> {
-        let mut labels = self.labels.write().await;
+        self.labels.update(|labels| labels.extend(expected_labels.clone())).await;
 
-        labels.extend(expected_labels.clone());
+        return Ok(());
+    }
 
-        Self::save(&self.path, &labels).await?;
+    async fn rebuild(&self, documents: &[(String, HashSet<Label>)]) -> Result<()> {
+        let rebuilt = documents.iter()
+            .flat_map(|(_, labels)| labels.iter().cloned())
+            .collect();
+
+        self.labels.update(|labels| *labels = rebuilt).await;
 
         return Ok(());
     }
+
+    async fn flush(&self) -> Result<()> {
+        return self.labels.flush().await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::suggester::Suggester as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn migrate() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let labels = vec![Label::from("first"), Label::from("second")].into_iter().collect::<HashSet<_>>();
+
+        let legacy = tmp.path().join("legacy");
+        std::fs::write(&legacy, bincode::serialize(&labels).unwrap()).unwrap();
+
+        let suggester = Suggester::from_config(Config {
+            path: Some(legacy.display().to_string()),
+        }, tmp.path().join("model")).await.unwrap();
+        assert_eq!(suggester.labels().await, labels);
+
+        // The migrated model is used from now on
+        std::fs::remove_file(&legacy).unwrap();
+
+        let suggester = Suggester::from_config(Config {
+            path: Some(legacy.display().to_string()),
+        }, tmp.path().join("model")).await.unwrap();
+        assert_eq!(suggester.labels().await, labels);
+    }
 }
@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::{BayesicSuggester, DumbSuggester, Suggester as Config};
+use crate::proto::model::Label;
+use crate::repository::Repository;
+
+use super::Suggester;
+
+/// Number of folds used for cross-validation if not specified otherwise.
+pub const DEFAULT_FOLDS: usize = 5;
+
+/// Compares the labels suggested for documents with their actual labels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Confusion {
+    /// Number of documents where the label was suggested and assigned
+    pub true_positives: u64,
+
+    /// Number of documents where the label was suggested but not assigned
+    pub false_positives: u64,
+
+    /// Number of documents where the label was assigned but not suggested
+    pub false_negatives: u64,
+}
+
+impl Confusion {
+    /// The share of suggestions which were correct - `None` if the label was never suggested.
+    pub fn precision(&self) -> Option<f64> {
+        let suggested = self.true_positives + self.false_positives;
+        if suggested == 0 {
+            return None;
+        }
+
+        return Some(self.true_positives as f64 / suggested as f64);
+    }
+
+    /// The share of assigned labels which were suggested - `None` if the label was never assigned.
+    pub fn recall(&self) -> Option<f64> {
+        let assigned = self.true_positives + self.false_negatives;
+        if assigned == 0 {
+            return None;
+        }
+
+        return Some(self.true_positives as f64 / assigned as f64);
+    }
+
+    /// Number of documents having the label.
+    pub fn support(&self) -> u64 {
+        return self.true_positives + self.false_negatives;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub documents: usize,
+    pub labels: HashMap<Label, Confusion>,
+}
+
+/// How often a suggested label has been accepted or rejected by the reviewer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Feedback {
+    pub accepted: u64,
+    pub rejected: u64,
+}
+
+/// Cross-validates a suggester over the given documents.
+///
+/// The documents are distributed over the given number of folds. For each fold, a fresh suggester is created by the
+/// factory, trained with the documents of all other folds and asked for suggestions on the documents of the fold.
+pub async fn evaluate<F, Fut>(documents: &[(String, HashSet<Label>)], folds: usize, factory: F) -> Result<Evaluation>
+    where F: Fn(usize) -> Fut,
+          Fut: Future<Output=Result<Box<dyn Suggester + Send + Sync>>> {
+    if folds < 2 {
+        return Err(anyhow!("Cross-validation requires at least two folds"));
+    }
+
+    let mut evaluation = Evaluation {
+        documents: documents.len(),
+        labels: HashMap::new(),
+    };
+
+    for fold in 0..folds {
+        info!("Evaluating fold {} of {}", fold + 1, folds);
+
+        let suggester = factory(fold).await?;
+
+        for (_, (plaintext, labels)) in documents.iter().enumerate().filter(|(i, _)| i % folds != fold) {
+            suggester.train(plaintext, labels).await?;
+        }
+
+        for (_, (plaintext, labels)) in documents.iter().enumerate().filter(|(i, _)| i % folds == fold) {
+            let suggested = suggester.guess(plaintext, None).await?.into_iter()
+                .map(|suggestion| suggestion.label)
+                .collect::<HashSet<_>>();
+
+            for label in suggested.union(labels) {
+                let confusion = evaluation.labels.entry(label.clone()).or_default();
+                match (suggested.contains(label), labels.contains(label)) {
+                    (true, true) => confusion.true_positives += 1,
+                    (true, false) => confusion.false_positives += 1,
+                    (false, _) => confusion.false_negatives += 1,
+                }
+            }
+        }
+    }
+
+    return Ok(evaluation);
+}
+
+/// Counts the accepted and rejected suggestions of all archived documents.
+pub async fn feedback(repository: &Repository) -> Result<HashMap<Label, Feedback>> {
+    let mut feedback = HashMap::<Label, Feedback>::new();
+
+    for bundle in repository.archive().list().await? {
+        let metadata = bundle.read_metadata().await?;
+
+        for label in metadata.suggested {
+            let entry = feedback.entry(label.clone()).or_default();
+            if metadata.labels.contains(&label) {
+                entry.accepted += 1;
+            } else {
+                entry.rejected += 1;
+            }
+        }
+    }
+
+    return Ok(feedback);
+}
+
+/// Evaluates the configured suggester without touching its model.
+///
+/// The evaluations are kept until the archive changes. The cross-validation runs on a blocking thread as it trains a
+/// model per fold.
+pub struct Evaluator {
+    config: Config,
+
+    /// Incremented whenever archived documents or their labels change
+    generation: AtomicU64,
+
+    /// The evaluations per number of folds and the generation they are valid for
+    cache: Mutex<(u64, HashMap<usize, Evaluation>)>,
+}
+
+impl Evaluator {
+    pub fn from_config(config: Config) -> Self {
+        return Self {
+            config,
+            generation: AtomicU64::new(0),
+            cache: Mutex::new((0, HashMap::new())),
+        };
+    }
+
+    /// Discards all evaluations after the archive has changed.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub async fn evaluate(&self, repository: &Repository, folds: usize) -> Result<Evaluation> {
+        // Changes made while evaluating are not covered by the evaluation, so the generation is taken before
+        let generation = self.generation.load(Ordering::SeqCst);
+
+        {
+            let cache = self.cache.lock().await;
+            if let Some(evaluation) = cache.1.get(&folds).filter(|_| cache.0 == generation) {
+                return Ok(evaluation.clone());
+            }
+        }
+
+        let evaluation = self.cross_validate(repository, folds).await?;
+
+        let mut cache = self.cache.lock().await;
+        if cache.0 != generation {
+            // Evaluations of an older generation are outdated, while those of a newer one must be kept
+            if cache.0 > generation {
+                return Ok(evaluation);
+            }
+
+            *cache = (generation, HashMap::new());
+        }
+        cache.1.insert(folds, evaluation.clone());
+
+        return Ok(evaluation);
+    }
+
+    async fn cross_validate(&self, repository: &Repository, folds: usize) -> Result<Evaluation> {
+        let documents = super::documents(repository).await?;
+
+        // Never migrate existing models into the temporary ones
+        let config = match self.config.clone() {
+            Config::Dumb(_) => Config::Dumb(DumbSuggester { path: None }),
+            Config::Bayesic(config) => Config::Bayesic(BayesicSuggester { path: None, ..config }),
+        };
+
+        // Each fold gets its own model stored in a temporary directory
+        let path = std::env::temp_dir().join(format!("adacta-evaluation-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&path).await?;
+
+        // Training a model per fold would block the executor
+        let result = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || futures::executor::block_on(evaluate(&documents, folds, |fold| {
+                return super::from_config(config.clone(), path.join(format!("fold-{}", fold)));
+            }))
+        }).await;
+
+        tokio::fs::remove_dir_all(&path).await?;
+
+        return result?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncWriteExt;
+
+    use crate::config::Pipeline;
+    use crate::meta::Metadata;
+    use crate::proto::model::Kind;
+
+    use super::*;
+
+    async fn archive(repository: &Repository, plaintext: &str, labels: &[&str], suggested: &[&str]) {
+        let staging = repository.stage().await.unwrap();
+
+        staging.write(Kind::Plaintext).await.unwrap()
+            .write_all(plaintext.as_bytes()).await.unwrap();
+
+        Metadata {
+            labels: labels.iter().map(|&label| Label::from(label)).collect(),
+            suggested: suggested.iter().map(|&label| Label::from(label)).collect(),
+            ..Metadata::new()
+        }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+        staging.create().await.unwrap()
+            .archive().await.unwrap();
+    }
+
+    #[test]
+    fn test_confusion() {
+        let confusion = Confusion { true_positives: 3, false_positives: 1, false_negatives: 2 };
+
+        assert_eq!(confusion.precision(), Some(0.75));
+        assert_eq!(confusion.recall(), Some(0.6));
+        assert_eq!(confusion.support(), 5);
+
+        assert_eq!(Confusion::default().precision(), None);
+        assert_eq!(Confusion::default().recall(), None);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        for i in 0..10 {
+            archive(&repository, &format!("insurance policy premium coverage claim {}", i), &["Insurance"], &[]).await;
+            archive(&repository, &format!("electricity meter tariff consumption grid {}", i), &["Energy"], &[]).await;
+        }
+
+        let evaluator = Evaluator::from_config(Config::Bayesic(BayesicSuggester {
+            path: None,
+            certainty: 0.5,
+            pipeline: Pipeline::default(),
+        }));
+
+        let evaluation = evaluator.evaluate(&repository, DEFAULT_FOLDS).await.unwrap();
+
+        assert_eq!(evaluation.documents, 20);
+        assert_eq!(evaluation.labels.len(), 2);
+
+        for confusion in evaluation.labels.values() {
+            assert_eq!(confusion.support(), 10);
+            assert_eq!(confusion.precision(), Some(1.0));
+            assert_eq!(confusion.recall(), Some(1.0));
+        }
+
+        // The repository is not touched by the evaluation
+        assert!(!repository.data_path().exists());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_cached() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let evaluator = Evaluator::from_config(Config::Dumb(DumbSuggester {
+            path: None,
+        }));
+
+        archive(&repository, "first", &["a"], &[]).await;
+        assert_eq!(evaluator.evaluate(&repository, DEFAULT_FOLDS).await.unwrap().documents, 1);
+
+        // The evaluation is kept until invalidated
+        archive(&repository, "second", &["a"], &[]).await;
+        assert_eq!(evaluator.evaluate(&repository, DEFAULT_FOLDS).await.unwrap().documents, 1);
+        assert_eq!(evaluator.evaluate(&repository, 3).await.unwrap().documents, 2);
+
+        evaluator.invalidate();
+        assert_eq!(evaluator.evaluate(&repository, DEFAULT_FOLDS).await.unwrap().documents, 2);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_folds() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        let evaluator = Evaluator::from_config(Config::Dumb(DumbSuggester {
+            path: None,
+        }));
+
+        assert!(evaluator.evaluate(&repository, 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_feedback() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repository = Repository::with_path(tmp.path().join("repository")).await.unwrap();
+
+        archive(&repository, "first", &["a", "b"], &["a", "c"]).await;
+        archive(&repository, "second", &["a"], &["a", "b"]).await;
+
+        let feedback = feedback(&repository).await.unwrap();
+
+        assert_eq!(feedback.get("a"), Some(&Feedback { accepted: 2, rejected: 0 }));
+        assert_eq!(feedback.get("b"), Some(&Feedback { accepted: 0, rejected: 1 }));
+        assert_eq!(feedback.get("c"), Some(&Feedback { accepted: 0, rejected: 1 }));
+    }
+}
@@ -1,21 +1,116 @@
 use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use log::info;
 #[cfg(test)]
 use mockall::automock;
+use tokio::sync::{Mutex, MutexGuard};
 
-use crate::proto::model::Label;
+use crate::config::Suggester as Config;
+use crate::proto::model::{Label, Suggestion};
+use crate::repository::Repository;
 
 pub mod bayesian;
 pub mod dumb;
+pub mod evaluation;
+mod pipeline;
 
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Suggester {
     async fn labels(&self) -> HashSet<Label>;
 
-    async fn guess(&self, text: &str) -> Result<HashSet<Label>>;
+    /// Whether the model must be re-trained from the archive, i.e. because it has been built by an incompatible
+    /// version.
+    async fn requires_training(&self) -> bool;
+
+    /// Proposes labels for a text sorted by descending score.
+    ///
+    /// Only labels scoring at least the given certainty are proposed. The configured default is used if no certainty
+    /// is given.
+    async fn guess(&self, text: &str, certainty: Option<f64>) -> Result<Vec<Suggestion>>;
     // TODO: Can this be a stream of tokens or a reader?
     async fn train(&self, text: &str, expected_labels: &HashSet<Label>) -> Result<()>;
+
+    /// Trains a new model with the given texts and labels and replaces the current model once finished, i.e. to
+    /// re-train the model after labels have been changed.
+    ///
+    /// The current model keeps answering guesses in the meantime.
+    async fn rebuild(&self, documents: &[(String, HashSet<Label>)]) -> Result<()>;
+
+    /// Persists all pending changes to the model.
+    async fn flush(&self) -> Result<()>;
+}
+
+/// Creates the configured suggester storing its model in the given directory.
+pub async fn from_config(config: Config, path: impl AsRef<Path>) -> Result<Box<dyn Suggester + Send + Sync>> {
+    return Ok(match config {
+        Config::Dumb(config) => Box::new(dumb::Suggester::from_config(config, path).await?),
+        Config::Bayesic(config) => Box::new(bayesian::Suggester::from_config(config, path).await?),
+    });
+}
+
+/// Extends the labels by all their parents.
+fn with_ancestors(labels: &HashSet<Label>) -> HashSet<Label> {
+    return labels.iter()
+        .flat_map(Label::ancestors)
+        .collect();
+}
+
+/// Drops all suggestions for labels having a child which has been suggested, too.
+fn most_specific(suggestions: Vec<Suggestion>) -> Vec<Suggestion> {
+    let labels = suggestions.iter()
+        .map(|suggestion| suggestion.label.clone())
+        .collect::<Vec<_>>();
+
+    return suggestions.into_iter()
+        .filter(|suggestion| !labels.iter().any(|label| label.is_descendant_of(&suggestion.label)))
+        .collect();
+}
+
+/// Reads the plaintext and labels of all documents in the archive ordered by their ID.
+pub async fn documents(repository: &Repository) -> Result<Vec<(String, HashSet<Label>)>> {
+    let mut bundles = repository.archive().list().await?;
+    bundles.sort_by_key(|bundle| *bundle.id());
+
+    let mut documents = Vec::with_capacity(bundles.len());
+    for bundle in bundles {
+        let metadata = bundle.read_metadata().await?;
+        let plaintext = bundle.read_plaintext().await?;
+
+        documents.push((plaintext, metadata.labels));
+    }
+
+    return Ok(documents);
+}
+
+/// Replaces the model of the suggester by a model trained with all documents in the archive.
+///
+/// The current model is kept if any of the documents can not be read. The model is trained on a blocking thread to
+/// keep the executor responsive.
+pub async fn retrain(suggester: Arc<dyn Suggester + Send + Sync>, repository: &Repository) -> Result<()> {
+    let documents = documents(repository).await?;
+
+    info!("Training suggester with {} archived documents", documents.len());
+
+    return tokio::task::spawn_blocking(move || futures::executor::block_on(async {
+        suggester.rebuild(&documents).await?;
+        return suggester.flush().await;
+    })).await?;
+}
+
+/// Serializes training the suggester with newly archived documents and re-training it from the archive.
+///
+/// A document archived while re-training would otherwise be trained twice or get lost once the re-trained model
+/// replaces the current one.
+#[derive(Default)]
+pub struct Training(Mutex<()>);
+
+impl Training {
+    pub async fn lock(&self) -> MutexGuard<'_, ()> {
+        return self.0.lock().await;
+    }
 }
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::{Language, Pipeline as Config};
+
+/// Version of the text pipeline.
+///
+/// Must be increased whenever the tokens produced for the same text and configuration change, as models trained with
+/// other tokens are worthless.
+const VERSION: u32 = 1;
+
+/// Token replacing all numbers if masking is enabled.
+const NUMBER: &str = "#";
+
+/// Identifies the pipeline a model has been trained with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    version: u32,
+    config: Config,
+}
+
+struct Dictionary {
+    stopwords: HashSet<&'static str>,
+    stemmer: Stemmer,
+}
+
+impl Dictionary {
+    fn new(language: &Language) -> Self {
+        let (stopwords, algorithm) = match language {
+            Language::German => (include_str!("stopwords/german.txt"), Algorithm::German),
+            Language::English => (include_str!("stopwords/english.txt"), Algorithm::English),
+        };
+
+        return Self {
+            stopwords: stopwords.lines().collect(),
+            stemmer: Stemmer::create(algorithm),
+        };
+    }
+}
+
+/// Turns texts into tokens used as features for classification.
+///
+/// The text is normalized (NFKC) and lowercased and split into words at all non-alphanumeric characters, which
+/// separates hyphenated compounds, too. The language of the text is guessed from the number of stopwords. Stopwords of
+/// all languages are dropped, numbers are masked and the remaining words are stemmed and stripped of diacritics (like
+/// umlauts).
+pub struct Pipeline {
+    config: Config,
+    dictionaries: Vec<Dictionary>,
+}
+
+impl Pipeline {
+    pub fn new(config: Config) -> Self {
+        let dictionaries = config.languages.iter()
+            .map(Dictionary::new)
+            .collect();
+
+        return Self { config, dictionaries };
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        return Fingerprint {
+            version: VERSION,
+            config: self.config.clone(),
+        };
+    }
+
+    pub fn tokenize(&self, text: &str) -> HashMap<String, u64> {
+        let text = text.nfkc().collect::<String>().to_lowercase();
+
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        // Use the language having the most stopwords in the text (the first one on a tie)
+        let mut dictionary = None;
+        let mut hits = 0;
+        for candidate in &self.dictionaries {
+            let count = words.iter().filter(|word| candidate.stopwords.contains(*word)).count();
+            if dictionary.is_none() || count > hits {
+                dictionary = Some(candidate);
+                hits = count;
+            }
+        }
+
+        let mut tokens = HashMap::new();
+        for word in words {
+            if self.dictionaries.iter().any(|dictionary| dictionary.stopwords.contains(word)) {
+                continue;
+            }
+
+            let token = if self.config.mask_numbers && word.chars().all(char::is_numeric) {
+                String::from(NUMBER)
+            } else {
+                if word.chars().count() < self.config.min_length {
+                    continue;
+                }
+
+                let word = match dictionary {
+                    Some(dictionary) if self.config.stemming => dictionary.stemmer.stem(word),
+                    _ => word.into(),
+                };
+
+                fold(&word)
+            };
+
+            *tokens.entry(token).or_insert(0) += 1;
+        }
+
+        return tokens;
+    }
+}
+
+/// Strips diacritics from a word.
+fn fold(word: &str) -> String {
+    return word.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .replace('ß', "ss");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let pipeline = Pipeline::new(Config::default());
+
+        let tokens = pipeline.tokenize("Die Rechnungen für die Kfz-Versicherung vom 01.02.2020 über 12,50 Euro.");
+
+        assert_eq!(tokens, vec![
+            (String::from("rechnung"), 1),
+            (String::from("kfz"), 1),
+            (String::from("versicher"), 1),
+            (String::from("#"), 5),
+            (String::from("euro"), 1),
+        ].into_iter().collect());
+    }
+
+    #[test]
+    fn test_tokenize_unstemmed() {
+        let pipeline = Pipeline::new(Config {
+            languages: vec![Language::English],
+            stemming: false,
+            min_length: 2,
+            mask_numbers: false,
+        });
+
+        let tokens = pipeline.tokenize("The Invoices of Müller & Co. from 2020");
+
+        assert_eq!(tokens, vec![
+            (String::from("invoices"), 1),
+            (String::from("muller"), 1),
+            (String::from("co"), 1),
+            (String::from("2020"), 1),
+        ].into_iter().collect());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let pipeline = Pipeline::new(Config::default());
+
+        assert_eq!(pipeline.fingerprint(), Pipeline::new(Config::default()).fingerprint());
+        assert_ne!(pipeline.fingerprint(), Pipeline::new(Config { stemming: false, ..Config::default() }).fingerprint());
+    }
+}
@@ -1,23 +1,28 @@
+use std::collections::HashSet;
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use chrono::NaiveDate;
 use rocket::{get, http::ContentType, State};
 use rocket::http::RawStr;
 use rocket::response::{Content, Stream};
 use rocket_contrib::json::Json;
 use tokio::io::AsyncRead;
 
-use crate::index::Index;
+use crate::index::{DateRange, Index, PropertyRange};
 use crate::proto::api::archive::{BundleResponse, SearchResponse};
-use crate::proto::model::{DocId, Kind};
+use crate::proto::model::{DocId, Kind, Label, Scope};
 use crate::repository::Repository;
+use crate::schema::Schema;
 
-use super::{ApiError, InternalError, Token};
+use super::{ApiError, InternalError, require, Token};
 
 #[get("/archive/<id>")]
 pub(super) async fn bundle(id: &RawStr,
                            repository: State<'_, Repository>,
-                           _token: &'_ Token) -> Result<Json<BundleResponse>, ApiError> {
+                           token: &'_ Token) -> Result<Json<BundleResponse>, ApiError> {
+    require(token, Scope::Read)?;
+
     let id = DocId::from_str(id.as_str())?;
 
     let bundle = repository.archive().get(id).await
@@ -34,13 +39,15 @@ pub(super) async fn bundle(id: &RawStr,
 pub(super) async fn fragment(id: &RawStr,
                              fragment: String,
                              repository: State<'_, Repository>,
-                             _token: &'_ Token) -> Result<Content<Stream<impl AsyncRead>>, ApiError> {
+                             token: &'_ Token) -> Result<Content<Stream<impl AsyncRead>>, ApiError> {
+    require(token, Scope::Read)?;
+
     let id = DocId::from_str(id.as_str())?;
     let kind = Kind::from(fragment.as_str());
 
     let content_type = match kind {
         Kind::Document => ContentType::PDF,
-        Kind::Preview => ContentType::PNG,
+        Kind::Preview | Kind::PagePreview { .. } | Kind::Thumbnail { .. } => ContentType::PNG,
         Kind::Plaintext => ContentType::Plain,
         Kind::Metadata => ContentType::JSON,
         Kind::Other { .. } => ContentType::Any,
@@ -56,12 +63,88 @@ pub(super) async fn fragment(id: &RawStr,
     return Ok(Content(content_type, file.into()));
 }
 
-#[get("/archive?<query>")]
+#[get("/archive/<id>/preview/<page>")]
+pub(super) async fn preview(id: &RawStr,
+                            page: u32,
+                            repository: State<'_, Repository>,
+                            token: &'_ Token) -> Result<Content<Stream<impl AsyncRead>>, ApiError> {
+    require(token, Scope::Read)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.archive().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let file = bundle.read(Kind::page_preview(page)).await
+        .map_err(InternalError)?
+        .ok_or_else(|| ApiError::not_found(format!("Preview not found: {}/preview/{}", id, page)))?;
+
+    return Ok(Content(ContentType::PNG, file.into()));
+}
+
+#[get("/archive/<id>/thumbnail/<page>")]
+pub(super) async fn thumbnail(id: &RawStr,
+                              page: u32,
+                              repository: State<'_, Repository>,
+                              token: &'_ Token) -> Result<Content<Stream<impl AsyncRead>>, ApiError> {
+    require(token, Scope::Read)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.archive().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let file = bundle.read(Kind::thumbnail(page)).await
+        .map_err(InternalError)?
+        .ok_or_else(|| ApiError::not_found(format!("Thumbnail not found: {}/thumbnail/{}", id, page)))?;
+
+    return Ok(Content(ContentType::PNG, file.into()));
+}
+
+#[get("/archive?<query>&<from>&<to>&<label>&<property>&<min>&<max>")]
 pub(super) async fn search(query: &RawStr,
+                           from: Option<&RawStr>,
+                           to: Option<&RawStr>,
+                           label: Option<String>,
+                           property: Option<String>,
+                           min: Option<&RawStr>,
+                           max: Option<&RawStr>,
                            index: State<'_, Box<dyn Index + Send + Sync>>,
                            repository: State<'_, Repository>,
-                           _token: &'_ Token) -> Result<Json<SearchResponse>, ApiError> {
-    let response = index.search(query).await?;
+                           schema: State<'_, Schema>,
+                           token: &'_ Token) -> Result<Json<SearchResponse>, ApiError> {
+    require(token, Scope::Read)?;
+
+    let dates = DateRange {
+        from: from.map(parse_date).transpose()?,
+        to: to.map(parse_date).transpose()?,
+    };
+
+    let properties = match property {
+        Some(key) => {
+            let bound = |value: &RawStr| schema.bound(&key, value.as_str())
+                .map_err(|err| ApiError::bad_request(format!("Invalid bound {}: {}", value, err)));
+
+            vec![PropertyRange {
+                min: min.map(bound).transpose()?,
+                max: max.map(bound).transpose()?,
+                key,
+            }]
+        }
+
+        None if min.is_some() || max.is_some() => {
+            return Err(ApiError::bad_request(String::from("Range without property")));
+        }
+
+        None => vec![],
+    };
+
+    // Parent labels match all their children
+    let labels = label.into_iter()
+        .map(Label::from)
+        .collect::<HashSet<_>>();
+
+    let response = index.search(query, &dates, &properties, &labels).await?;
 
     // TODO: Can this be a done as stream?
     let mut docs = Vec::new();
@@ -79,3 +162,8 @@ pub(super) async fn search(query: &RawStr,
         docs,
     }))
 }
+
+fn parse_date(s: &RawStr) -> Result<NaiveDate, ApiError> {
+    return NaiveDate::parse_from_str(s.as_str(), "%Y-%m-%d")
+        .map_err(|err| ApiError::bad_request(format!("Invalid date {}: {}", s, err)));
+}
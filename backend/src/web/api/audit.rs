@@ -0,0 +1,20 @@
+use rocket::{get, State};
+use rocket_contrib::json::Json;
+
+use crate::audit::Audit;
+use crate::proto::api::audit::ListResponse;
+use crate::proto::model::Scope;
+
+use super::{ApiError, require, Token};
+
+#[get("/audit")]
+pub(super) async fn list(audit: State<'_, Audit>,
+                         token: &'_ Token) -> Result<Json<ListResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let entries = audit.entries().await?;
+
+    return Ok(Json(ListResponse {
+        entries,
+    }));
+}
@@ -1,16 +1,20 @@
 use async_trait::async_trait;
 use log::info;
-use rocket::{Data, post, Request, Response, State};
+use rocket::{Data, post, Request, State};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::http::{Header, Status};
 use rocket::request::{FromRequest, Outcome};
+use rocket::response::Responder;
 use rocket_contrib::json::Json;
 
 use crate::auth::Authenticator;
 pub use crate::auth::Token;
-use crate::proto::api::auth::AuthRequest;
+use crate::proto::api::auth::{AuthRequest, AuthResponse, RefreshRequest};
+use crate::proto::model::Scope;
 use crate::utils::StrExt;
 
+use super::ApiError;
+
 pub struct Authorization {}
 
 #[async_trait]
@@ -18,7 +22,7 @@ impl Fairing for Authorization {
     fn info(&self) -> Info {
         Info {
             name: "Authorization",
-            kind: Kind::Request | Kind::Response,
+            kind: Kind::Request,
         }
     }
 
@@ -53,22 +57,6 @@ impl Fairing for Authorization {
             }
         }).await;
     }
-
-    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
-        let token = request.local_cache_async::<Option<Token>, _>(async {
-            return None;
-        }).await;
-
-        if let Some(token) = token {
-            let auth = request.guard::<State<'_, Authenticator>>().await
-                .expect("No Authenticator");
-
-            let bearer = auth.sign_token(token).await
-                .expect("Can not sign token");
-
-            response.set_header(Header::new("Authorization", bearer));
-        }
-    }
 }
 
 #[async_trait::async_trait]
@@ -87,21 +75,61 @@ impl<'a, 'r> FromRequest<'a, 'r> for &'a Token {
     }
 }
 
+/// Rejects the request unless the token has been granted the scope.
+pub(super) fn require(token: &Token, scope: Scope) -> Result<(), ApiError> {
+    if !token.has(scope) {
+        return Err(ApiError::forbidden(format!("Missing scope: {}", scope)));
+    }
+
+    return Ok(());
+}
+
+/// The tokens of a session - the access token is additionally returned as header.
+#[derive(Responder)]
+#[response(status = 202)]
+pub(super) struct SessionResponse {
+    tokens: Json<AuthResponse>,
+    authorization: Header<'static>,
+}
+
+impl From<AuthResponse> for SessionResponse {
+    fn from(tokens: AuthResponse) -> Self {
+        return Self {
+            authorization: Header::new("Authorization", tokens.access.clone()),
+            tokens: Json(tokens),
+        };
+    }
+}
+
 #[post("/auth/login", data = "<request>")]
 pub(super) async fn login(auth: State<'_, Authenticator>,
-                          request: Json<AuthRequest>) -> Response<'_> {
-    if let Some(token) = auth.login(&request.password).await {
-        info!("Login successful");
-
-        let bearer = auth.sign_token(&token).await.expect("Can not sign token");
-
-        return Response::build()
-            .header(Header::new("Authorization", bearer))
-            .status(Status::Accepted)
-            .finalize();
-    } else {
-        return Response::build()
-            .status(Status::BadRequest)
-            .finalize();
-    }
+                          request: Json<AuthRequest>) -> Result<SessionResponse, ApiError> {
+    let token = auth.login(request.username.as_deref(), &request.password, request.scopes.as_ref()).await
+        .ok_or_else(|| ApiError::bad_request(String::from("Invalid username or password")))?;
+
+    info!("Login successful: {}", token.username());
+
+    return Ok(auth.issue(&token).await?.into());
+}
+
+/// Issues new tokens for a session - the client must log in again if the session can not be continued.
+#[post("/auth/refresh", data = "<request>")]
+pub(super) async fn refresh(auth: State<'_, Authenticator>,
+                            request: Json<RefreshRequest>) -> Result<SessionResponse, ApiError> {
+    let token = auth.refresh(&request.refresh).await
+        .map_err(|err| ApiError::unauthorized(format!("Invalid refresh token: {}", err)))?;
+
+    let tokens = auth.issue(&token).await
+        .map_err(|err| ApiError::unauthorized(format!("{}", err)))?;
+
+    return Ok(tokens.into());
+}
+
+#[post("/auth/logout")]
+pub(super) async fn logout(auth: State<'_, Authenticator>,
+                           token: &'_ Token) -> Result<(), ApiError> {
+    auth.logout(token).await
+        .map_err(|err| ApiError::bad_request(format!("{}", err)))?;
+
+    return Ok(());
 }
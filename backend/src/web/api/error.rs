@@ -3,7 +3,9 @@ use log::error;
 use rocket::{Request, Response};
 use rocket::http::Status;
 use rocket::response::Responder;
-use rocket::response::status::NotFound;
+use rocket::response::status::{Custom, NotFound};
+
+use crate::juicer::ValidationError;
 
 #[derive(Debug)]
 pub(super) struct InternalError(pub Error);
@@ -27,12 +29,24 @@ impl From<Error> for InternalError {
 
 #[derive(Debug, Responder)]
 pub(super) enum ApiError {
+    BadRequest(Custom<String>),
+    Unauthorized(Custom<String>),
+    Forbidden(Custom<String>),
     NotFound(NotFound<String>),
+    UnprocessableEntity(Custom<String>),
     InternalError(InternalError),
 }
 
 impl ApiError {
+    pub const fn bad_request(s: String) -> Self { Self::BadRequest(Custom(Status::BadRequest, s)) }
+
+    pub const fn unauthorized(s: String) -> Self { Self::Unauthorized(Custom(Status::Unauthorized, s)) }
+
+    pub const fn forbidden(s: String) -> Self { Self::Forbidden(Custom(Status::Forbidden, s)) }
+
     pub const fn not_found(s: String) -> Self { Self::NotFound(NotFound(s)) }
+
+    pub const fn unprocessable_entity(s: String) -> Self { Self::UnprocessableEntity(Custom(Status::UnprocessableEntity, s)) }
 }
 
 impl From<NotFound<String>> for ApiError {
@@ -44,5 +58,12 @@ impl From<InternalError> for ApiError {
 }
 
 impl From<Error> for ApiError {
-    fn from(err: Error) -> Self { Self::InternalError(err.into()) }
+    fn from(err: Error) -> Self {
+        // The juicer rejected the uploaded document - this is caused by the input rather than by the server
+        if err.downcast_ref::<ValidationError>().is_some() {
+            return Self::unprocessable_entity(format!("{:#}", err));
+        }
+
+        return Self::InternalError(err.into());
+    }
 }
@@ -1,7 +1,8 @@
 use std::str::FromStr;
+use std::sync::Arc;
 
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{Datelike, Utc};
 use futures::{StreamExt, TryStreamExt};
 use rocket::{delete, get, post, State};
 use rocket::http::{ContentType, RawStr};
@@ -9,18 +10,27 @@ use rocket::response::{Content, Stream};
 use rocket_contrib::json::Json;
 use tokio::io::AsyncRead;
 
+use crate::audit::{Action, Audit};
+use crate::extractor::Extractor;
 use crate::index::Index;
-use crate::proto::api::inbox::{ArchiveRequest, GetResponse, ListResponse};
-use crate::proto::model::{DocId, DocInfo, Kind};
-use crate::repository::Repository;
-use crate::suggester::Suggester;
+use crate::juicer::{assemble, edit, Juicer};
+use crate::labels::Labels;
+use crate::meta::Metadata;
+use crate::proto::api::inbox::{ArchiveRequest, EditRequest, EditResponse, GetResponse, ListResponse, MergeRequest, MergeResponse, SplitRequest, SplitResponse};
+use crate::proto::model::{DocId, DocInfo, Kind, Label, Scope};
+use crate::repository::{Bundle, Inboxed, Repository};
+use crate::schema::Schema;
+use crate::suggester::evaluation::Evaluator;
+use crate::suggester::{Suggester, Training};
 use crate::web::api::InternalError;
 
-use super::{ApiError, Token};
+use super::{ApiError, require, Token};
 
 #[get("/inbox")]
 pub(super) async fn list(repository: State<'_, Repository>,
-                         _token: &'_ Token) -> Result<Json<ListResponse>, ApiError> {
+                         token: &'_ Token) -> Result<Json<ListResponse>, ApiError> {
+    require(token, Scope::Review)?;
+
     let bundles = repository.inbox().list().await?;
 
     let docs = tokio::stream::iter(bundles.iter())
@@ -39,11 +49,15 @@ pub(super) async fn list(repository: State<'_, Repository>,
     }))
 }
 
-#[get("/inbox/<id>")]
+#[get("/inbox/<id>?<certainty>")]
 pub(super) async fn bundle(id: &RawStr,
+                           certainty: Option<f64>,
                            repository: State<'_, Repository>,
-                           suggester: State<'_, Box<dyn Suggester + Send + Sync>>,
-                           _token: &'_ Token) -> Result<Json<GetResponse>, ApiError> {
+                           suggester: State<'_, Arc<dyn Suggester + Send + Sync>>,
+                           extractor: State<'_, Extractor>,
+                           token: &'_ Token) -> Result<Json<GetResponse>, ApiError> {
+    require(token, Scope::Review)?;
+
     let id = DocId::from_str(id.as_str())?;
 
     let bundle = repository.inbox().get(id).await
@@ -52,11 +66,23 @@ pub(super) async fn bundle(id: &RawStr,
     let metadata = bundle.read_metadata().await?;
     let plaintext = bundle.read_plaintext().await?;
 
-    let suggestions = suggester.guess(&plaintext).await?;
+    if let Some(certainty) = certainty {
+        if !(0.0..=1.0).contains(&certainty) {
+            return Err(ApiError::bad_request(format!("Certainty must be between 0 and 1: {}", certainty)));
+        }
+    }
+
+    let suggestions = suggester.guess(&plaintext, certainty).await?;
+    let date_suggestions = extractor.dates(&plaintext);
+    let property_suggestions = extractor.properties(&plaintext);
+    let title_suggestion = extractor.title(&plaintext);
 
     return Ok(Json(GetResponse {
         doc: (id, metadata).into(),
         suggestions,
+        date_suggestions,
+        property_suggestions,
+        title_suggestion,
     }));
 }
 
@@ -64,13 +90,15 @@ pub(super) async fn bundle(id: &RawStr,
 pub(super) async fn fragment<'r>(id: &RawStr,
                              fragment: &RawStr,
                              repository: State<'r, Repository>,
-                             _token: &'_ Token) -> Result<Content<Stream<impl AsyncRead + 'r>>, ApiError> {
+                             token: &'_ Token) -> Result<Content<Stream<impl AsyncRead + 'r>>, ApiError> {
+    require(token, Scope::Review)?;
+
     let id = DocId::from_str(id.as_str())?;
     let kind = Kind::from(fragment.as_str());
 
     let content_type = match kind {
         Kind::Document => ContentType::PDF,
-        Kind::Preview => ContentType::PNG,
+        Kind::Preview | Kind::PagePreview { .. } | Kind::Thumbnail { .. } => ContentType::PNG,
         Kind::Plaintext => ContentType::Plain,
         Kind::Metadata => ContentType::JSON,
         Kind::Other { .. } => ContentType::Any,
@@ -86,16 +114,59 @@ pub(super) async fn fragment<'r>(id: &RawStr,
     return Ok(Content(content_type, file.into()));
 }
 
+#[get("/inbox/<id>/preview/<page>")]
+pub(super) async fn preview<'r>(id: &RawStr,
+                                page: u32,
+                                repository: State<'r, Repository>,
+                                token: &'_ Token) -> Result<Content<Stream<impl AsyncRead + 'r>>, ApiError> {
+    require(token, Scope::Review)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.inbox().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let file = bundle.read(Kind::page_preview(page)).await
+        .map_err(InternalError)?
+        .ok_or_else(|| ApiError::not_found(format!("Preview not found: {}/preview/{}", id, page)))?;
+
+    return Ok(Content(ContentType::PNG, file.into()));
+}
+
+#[get("/inbox/<id>/thumbnail/<page>")]
+pub(super) async fn thumbnail<'r>(id: &RawStr,
+                                  page: u32,
+                                  repository: State<'r, Repository>,
+                                  token: &'_ Token) -> Result<Content<Stream<impl AsyncRead + 'r>>, ApiError> {
+    require(token, Scope::Review)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.inbox().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let file = bundle.read(Kind::thumbnail(page)).await
+        .map_err(InternalError)?
+        .ok_or_else(|| ApiError::not_found(format!("Thumbnail not found: {}/thumbnail/{}", id, page)))?;
+
+    return Ok(Content(ContentType::PNG, file.into()));
+}
+
 #[delete("/inbox/<id>")]
 pub(super) async fn delete(id: &RawStr,
                            repository: State<'_, Repository>,
-                           _token: &'_ Token) -> Result<(), ApiError> {
+                           audit: State<'_, Audit>,
+                           token: &'_ Token) -> Result<(), ApiError> {
+    require(token, Scope::Review)?;
+
     let id = DocId::from_str(id.as_str())?;
 
     let bundle = repository.inbox().get(id).await
         .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
     bundle.delete().await?;
 
+    audit.report(token.username(), Action::Delete { doc: id }).await;
+
     return Ok(());
 }
 
@@ -104,32 +175,288 @@ pub(super) async fn archive(id: &RawStr,
                             data: Json<ArchiveRequest>,
                             repository: State<'_, Repository>,
                             index: State<'_, Box<dyn Index + Send + Sync>>,
-                            suggester: State<'_, Box<dyn Suggester + Send + Sync>>,
-                            _token: &'_ Token) -> Result<(), ApiError> {
+                            suggester: State<'_, Arc<dyn Suggester + Send + Sync>>,
+                            training: State<'_, Training>,
+                            labels: State<'_, Arc<Labels>>,
+                            schema: State<'_, Schema>,
+                            evaluator: State<'_, Evaluator>,
+                            audit: State<'_, Audit>,
+                            token: &'_ Token) -> Result<(), ApiError> {
+    require(token, Scope::Review)?;
+
     let id = DocId::from_str(id.as_str())?;
 
     let bundle = repository.inbox().get(id).await
         .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
 
+    validate(&data)?;
+
+    schema.validate(&data.labels, &data.properties)
+        .map_err(|err| ApiError::bad_request(format!("{:#}", err)))?;
+
     // Update the metadata
     let mut metadata = bundle.read_metadata().await?;
 
     metadata.archived = Some(Utc::now());
+    if let Some(title) = &data.title {
+        metadata.title = Some(title.clone());
+    }
+    if let Some(date) = data.date {
+        metadata.date = Some(date);
+    }
     metadata.labels = data.labels.clone();
     metadata.properties = data.properties.clone();
 
+    // Remember the labels proposed to the reviewer to track accepted and rejected suggestions
+    let plaintext = bundle.read_plaintext().await?;
+    metadata.suggested = suggester.guess(&plaintext, None).await?.into_iter()
+        .map(|suggestion| suggestion.label)
+        .collect();
+
     bundle.write_metadata(&metadata).await?;
 
+    // Re-training must either see the archived bundle or wait for it to be trained
+    let _training = training.lock().await;
+
     // Archive the bundle
     let archived = bundle.archive().await?;
 
     // Add the archived bundle to the index
     index.index(&archived).await?;
 
-    // Train the suggester with the final labels
-    let plaintext = archived.read_plaintext().await?;
+    // Count the labels of the archived bundle
+    labels.add(&metadata.labels).await;
+
+    // The archive has changed and must be evaluated again
+    evaluator.invalidate();
+
+    audit.report(token.username(), Action::Archive { doc: id }).await;
 
+    // Train the suggester with the final labels
     suggester.train(&plaintext, &metadata.labels).await?;
 
     return Ok(());
 }
+
+/// Maximum length of titles, labels, property keys and property values.
+const MAX_FIELD_LENGTH: usize = 256;
+
+/// Validates the metadata given by the reviewer.
+fn validate(request: &ArchiveRequest) -> Result<(), ApiError> {
+    if let Some(title) = &request.title {
+        validate_field("Title", title, false)?;
+    }
+
+    if let Some(date) = request.date {
+        if !(1900..=2100).contains(&date.year()) {
+            return Err(ApiError::bad_request(format!("Date out of range: {}", date)));
+        }
+    }
+
+    for label in &request.labels {
+        validate_label(label)?;
+    }
+
+    for (key, value) in &request.properties {
+        validate_field("Property key", key, false)?;
+        validate_field(&format!("Property {}", key), value, true)?;
+    }
+
+    return Ok(());
+}
+
+/// Validates a label and each of its levels.
+pub(super) fn validate_label(label: &Label) -> Result<(), ApiError> {
+    validate_field("Label", label.as_str(), false)?;
+
+    if label.segments().any(|segment| segment.trim().is_empty() || segment.trim() != segment) {
+        return Err(ApiError::bad_request(format!("Label must not contain empty levels or surrounding whitespace: {}", label)));
+    }
+
+    return Ok(());
+}
+
+pub(super) fn validate_field(name: &str, value: &str, allow_empty: bool) -> Result<(), ApiError> {
+    if !allow_empty && value.trim().is_empty() {
+        return Err(ApiError::bad_request(format!("{} must not be empty", name)));
+    }
+
+    if value.chars().count() > MAX_FIELD_LENGTH {
+        return Err(ApiError::bad_request(format!("{} must not be longer than {} characters", name, MAX_FIELD_LENGTH)));
+    }
+
+    if value.chars().any(char::is_control) {
+        return Err(ApiError::bad_request(format!("{} must not contain control characters", name)));
+    }
+
+    return Ok(());
+}
+
+#[post("/inbox/<id>/split", data = "<data>")]
+pub(super) async fn split(id: &RawStr,
+                          data: Json<SplitRequest>,
+                          repository: State<'_, Repository>,
+                          juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                          token: &'_ Token) -> Result<Json<SplitResponse>, ApiError> {
+    require(token, Scope::Review)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.inbox().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let metadata = bundle.read_metadata().await?;
+
+    if data.ranges.is_empty() {
+        return Err(ApiError::bad_request(String::from("No page ranges given")));
+    }
+
+    for range in &data.ranges {
+        if range.first == 0 || range.first > range.last || range.last > metadata.pages {
+            return Err(ApiError::bad_request(format!("Invalid page range {} for document with {} pages", range, metadata.pages)));
+        }
+    }
+
+    // The split documents are treated as if they had been uploaded along with the source document
+    let split_metadata = Metadata {
+        uploaded: metadata.uploaded,
+        ..Metadata::new()
+    };
+
+    let mut splits = Vec::with_capacity(data.ranges.len());
+    for range in &data.ranges {
+        match assemble(juicer.inner().as_ref(), repository.inner(), &split_metadata, &[(&bundle, Some(*range))]).await {
+            Ok(split) => splits.push(split),
+            Err(err) => {
+                // Remove the already split documents and keep the source bundle as is
+                for split in splits {
+                    split.delete().await?;
+                }
+
+                return Err(err.into());
+            }
+        }
+    }
+
+    // The source bundle is replaced by the split documents
+    bundle.delete().await?;
+
+    let mut docs = Vec::with_capacity(splits.len());
+    for split in splits {
+        docs.push(DocInfo {
+            id: *split.id(),
+            metadata: split.read_metadata().await?.into(),
+        });
+    }
+
+    return Ok(Json(SplitResponse { docs }));
+}
+
+#[post("/inbox/<id>/pages", data = "<data>")]
+pub(super) async fn pages(id: &RawStr,
+                          data: Json<EditRequest>,
+                          repository: State<'_, Repository>,
+                          juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                          token: &'_ Token) -> Result<Json<EditResponse>, ApiError> {
+    require(token, Scope::Review)?;
+
+    let id = DocId::from_str(id.as_str())?;
+
+    let bundle = repository.inbox().get(id).await
+        .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+    let metadata = bundle.read_metadata().await?;
+
+    if data.pages.is_empty() {
+        return Err(ApiError::bad_request(String::from("Document must keep at least one page")));
+    }
+
+    for edit in &data.pages {
+        if edit.page == 0 || edit.page > metadata.pages {
+            return Err(ApiError::bad_request(format!("Invalid page {} for document with {} pages", edit.page, metadata.pages)));
+        }
+
+        if edit.rotate % 90 != 0 || edit.rotate >= 360 {
+            return Err(ApiError::bad_request(format!("Invalid rotation {} for page {}", edit.rotate, edit.page)));
+        }
+    }
+
+    let bundle = edit(juicer.inner().as_ref(), bundle, &data.pages).await?;
+
+    let metadata = bundle.read_metadata().await?;
+
+    return Ok(Json(EditResponse {
+        doc: DocInfo {
+            id: *bundle.id(),
+            metadata: metadata.into(),
+        }
+    }));
+}
+
+#[post("/inbox/merge", data = "<data>")]
+pub(super) async fn merge(data: Json<MergeRequest>,
+                          repository: State<'_, Repository>,
+                          juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                          token: &'_ Token) -> Result<Json<MergeResponse>, ApiError> {
+    require(token, Scope::Review)?;
+
+    if data.ids.len() < 2 {
+        return Err(ApiError::bad_request(String::from("At least two documents are required for merging")));
+    }
+
+    let mut bundles = Vec::with_capacity(data.ids.len());
+    for id in &data.ids {
+        if bundles.iter().any(|bundle: &Bundle<Inboxed>| bundle.id() == id) {
+            return Err(ApiError::bad_request(format!("Document given multiple times: {}", id)));
+        }
+
+        let bundle = repository.inbox().get(*id).await
+            .ok_or_else(|| ApiError::not_found(format!("Bundle not found: {}", id)))?;
+
+        bundles.push(bundle);
+    }
+
+    // Combine the metadata of all documents - the first document wins for conflicting properties, the title and the date
+    let mut metadata = Metadata::new();
+    for (i, bundle) in bundles.iter().enumerate() {
+        let source = bundle.read_metadata().await?;
+
+        if i == 0 || source.uploaded < metadata.uploaded {
+            metadata.uploaded = source.uploaded;
+        }
+
+        if metadata.title.is_none() {
+            metadata.title = source.title;
+        }
+
+        if metadata.date.is_none() {
+            metadata.date = source.date;
+        }
+
+        metadata.labels.extend(source.labels);
+
+        for (key, value) in source.properties {
+            metadata.properties.entry(key).or_insert(value);
+        }
+    }
+
+    let sources = bundles.iter()
+        .map(|bundle| (bundle, None))
+        .collect::<Vec<_>>();
+
+    let merged = assemble(juicer.inner().as_ref(), repository.inner(), &metadata, &sources).await?;
+
+    // The source bundles are replaced by the merged document
+    for bundle in bundles {
+        bundle.delete().await?;
+    }
+
+    let metadata = merged.read_metadata().await?;
+
+    return Ok(Json(MergeResponse {
+        doc: DocInfo {
+            id: *merged.id(),
+            metadata: metadata.into(),
+        }
+    }));
+}
@@ -0,0 +1,77 @@
+use rocket::{delete, get, post, State};
+use rocket_contrib::json::Json;
+
+use crate::auth::Authenticator;
+use crate::proto::api::keys::{CreateRequest, CreateResponse, ListResponse};
+use crate::proto::model::Scope;
+
+use super::{ApiError, require, Token};
+
+/// Maximum length of the name of a key.
+const MAX_NAME_LENGTH: usize = 64;
+
+#[get("/keys")]
+pub(super) async fn list(auth: State<'_, Authenticator>,
+                         token: &'_ Token) -> Result<Json<ListResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let keys = auth.keys().list().await;
+
+    return Ok(Json(ListResponse { keys }));
+}
+
+#[post("/keys", data = "<data>")]
+pub(super) async fn create(data: Json<CreateRequest>,
+                           auth: State<'_, Authenticator>,
+                           token: &'_ Token) -> Result<Json<CreateResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let CreateRequest { name, scopes } = data.into_inner();
+
+    validate_name(&name)?;
+
+    if scopes.as_ref().map_or(false, |scopes| scopes.is_empty()) {
+        return Err(ApiError::bad_request(String::from("Key requires at least one scope")));
+    }
+
+    // A key can not be granted more than the creating session or key
+    let granted = scopes.clone().unwrap_or_else(Scope::all);
+    if !granted.is_subset(token.scopes()) {
+        return Err(ApiError::forbidden(String::from("Key can not be granted scopes missing on the creator")));
+    }
+
+    if auth.is_configured_key(&name) {
+        return Err(ApiError::bad_request(format!("Key already exists: {}", name)));
+    }
+
+    let secret = auth.keys().create(&name, scopes).await?
+        .ok_or_else(|| ApiError::bad_request(format!("Key already exists: {}", name)))?;
+
+    return Ok(Json(CreateResponse { name, secret }));
+}
+
+#[delete("/keys/<name>")]
+pub(super) async fn revoke(name: String,
+                           auth: State<'_, Authenticator>,
+                           token: &'_ Token) -> Result<(), ApiError> {
+    require(token, Scope::Admin)?;
+
+    if !auth.keys().revoke(&name).await? {
+        return Err(ApiError::not_found(format!("Key not found: {}", name)));
+    }
+
+    return Ok(());
+}
+
+/// Names are used as username for basic authentication and must not contain a colon.
+fn validate_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(ApiError::bad_request(format!("Key name must have 1 to {} characters", MAX_NAME_LENGTH)));
+    }
+
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') {
+        return Err(ApiError::bad_request(format!("Key name may only contain letters, digits, '-', '_' and '.': {}", name)));
+    }
+
+    return Ok(());
+}
@@ -1,19 +1,262 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
-use rocket::{get, State};
+use anyhow::anyhow;
+use rocket::{delete, get, post, put, State};
 use rocket_contrib::json::Json;
 
-use crate::proto::model::Label;
-use crate::suggester::Suggester;
+use crate::index::Index;
+use crate::labels::{Labels, Relabeled};
+use crate::proto::api::labels::{ChangeResponse, LabelResponse, LabelStats, ListResponse, MergeRequest, RenameRequest, StatsResponse};
+use crate::proto::model::{Label, LabelInfo, Scope};
+use crate::repository::Repository;
+use crate::suggester::evaluation::{DEFAULT_FOLDS, Evaluator, feedback};
+use crate::suggester::{retrain, Suggester, Training};
 
-use super::{ApiError, Token};
+use super::{ApiError, require, Token};
+use super::inbox::{validate_field, validate_label};
 
 #[get("/labels")]
-pub(super) async fn list(suggester: State<'_, Box<dyn Suggester + Send + Sync>>,
-                         _token: &'_ Token) -> Result<Json<HashSet<Label>>, ApiError> {
-    let labels = suggester.labels().await;
+pub(super) async fn list(labels: State<'_, Arc<Labels>>,
+                         token: &'_ Token) -> Result<Json<ListResponse>, ApiError> {
+    require(token, Scope::Read)?;
 
-    Ok(Json(labels))
+    let labels = tree(labels.all().await);
+
+    Ok(Json(ListResponse { labels }))
+}
+
+/// Arranges the labels as tree including all parents which are not used on their own.
+fn tree(labels: HashMap<Label, (u64, u64, LabelInfo)>) -> Vec<LabelResponse> {
+    let mut all = BTreeMap::new();
+    for (label, details) in labels {
+        for ancestor in label.ancestors() {
+            all.entry(ancestor).or_default();
+        }
+        all.insert(label, details);
+    }
+
+    fn children(all: &BTreeMap<Label, (u64, u64, LabelInfo)>, parent: Option<&Label>) -> Vec<LabelResponse> {
+        return all.iter()
+            .filter(|(label, _)| label.parent().as_ref() == parent)
+            .map(|(label, (count, total, info))| LabelResponse {
+                label: label.clone(),
+                count: *count,
+                total: *total,
+                info: info.clone(),
+                children: children(all, Some(label)),
+            })
+            .collect();
+    }
+
+    return children(&all, None);
+}
+
+#[put("/labels?<label>", data = "<data>")]
+pub(super) async fn describe(label: String,
+                             data: Json<LabelInfo>,
+                             labels: State<'_, Arc<Labels>>,
+                             token: &'_ Token) -> Result<Json<LabelResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let label = Label::from(label);
+    validate_label(&label)?;
+
+    let info = data.into_inner();
+
+    if let Some(description) = &info.description {
+        validate_field("Description", description, true)?;
+    }
+
+    if let Some(color) = &info.color {
+        if !is_color(color) {
+            return Err(ApiError::bad_request(format!("Color must be a hex RGB value like #1e90ff: {}", color)));
+        }
+    }
+
+    labels.describe(label.clone(), info.clone()).await?;
+
+    return Ok(Json(LabelResponse {
+        count: labels.count(&label).await,
+        total: labels.total(&label).await,
+        label,
+        info,
+        children: Vec::new(),
+    }));
+}
+
+#[post("/labels/rename?<label>", data = "<data>")]
+pub(super) async fn rename(label: String,
+                           data: Json<RenameRequest>,
+                           repository: State<'_, Repository>,
+                           index: State<'_, Box<dyn Index + Send + Sync>>,
+                           suggester: State<'_, Arc<dyn Suggester + Send + Sync>>,
+                           training: State<'_, Training>,
+                           labels: State<'_, Arc<Labels>>,
+                           evaluator: State<'_, Evaluator>,
+                           token: &'_ Token) -> Result<Json<ChangeResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let from = known(Label::from(label), labels.inner()).await?;
+    let to = data.into_inner().to;
+
+    validate_label(&to)?;
+
+    if labels.exists(&to).await {
+        return Err(ApiError::bad_request(format!("Label already exists: {}", to)));
+    }
+
+    let response = apply(&from, Some(&to), repository.inner(), index.inner().as_ref(), suggester.inner().clone(), training.inner(), labels.inner(), evaluator.inner()).await?;
+
+    return Ok(Json(response));
+}
+
+#[post("/labels/merge?<label>", data = "<data>")]
+pub(super) async fn merge(label: String,
+                          data: Json<MergeRequest>,
+                          repository: State<'_, Repository>,
+                          index: State<'_, Box<dyn Index + Send + Sync>>,
+                          suggester: State<'_, Arc<dyn Suggester + Send + Sync>>,
+                          training: State<'_, Training>,
+                          labels: State<'_, Arc<Labels>>,
+                          evaluator: State<'_, Evaluator>,
+                          token: &'_ Token) -> Result<Json<ChangeResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let from = known(Label::from(label), labels.inner()).await?;
+    let into = data.into_inner().into;
+
+    validate_label(&into)?;
+
+    if into == from || into.is_descendant_of(&from) {
+        return Err(ApiError::bad_request(format!("Label can not be merged into itself: {}", into)));
+    }
+
+    let response = apply(&from, Some(&into), repository.inner(), index.inner().as_ref(), suggester.inner().clone(), training.inner(), labels.inner(), evaluator.inner()).await?;
+
+    return Ok(Json(response));
+}
+
+#[delete("/labels?<label>")]
+pub(super) async fn delete(label: String,
+                           repository: State<'_, Repository>,
+                           index: State<'_, Box<dyn Index + Send + Sync>>,
+                           suggester: State<'_, Arc<dyn Suggester + Send + Sync>>,
+                           training: State<'_, Training>,
+                           labels: State<'_, Arc<Labels>>,
+                           evaluator: State<'_, Evaluator>,
+                           token: &'_ Token) -> Result<Json<ChangeResponse>, ApiError> {
+    require(token, Scope::Admin)?;
+
+    let label = known(Label::from(label), labels.inner()).await?;
+
+    let response = apply(&label, None, repository.inner(), index.inner().as_ref(), suggester.inner().clone(), training.inner(), labels.inner(), evaluator.inner()).await?;
+
+    return Ok(Json(response));
+}
+
+/// Ensures the label is used by an archived document or has been described.
+async fn known(label: Label, labels: &Labels) -> Result<Label, ApiError> {
+    if !labels.exists(&label).await {
+        return Err(ApiError::not_found(format!("Label not found: {}", label)));
+    }
+
+    return Ok(label);
+}
+
+/// Replaces or removes the label on all archived documents and updates the index and suggester accordingly.
+///
+/// Documents which can not be changed are reported after all other documents have been updated.
+async fn apply(from: &Label,
+               to: Option<&Label>,
+               repository: &Repository,
+               index: &(dyn Index + Send + Sync),
+               suggester: Arc<dyn Suggester + Send + Sync>,
+               training: &Training,
+               labels: &Labels,
+               evaluator: &Evaluator) -> Result<ChangeResponse, ApiError> {
+    let _training = training.lock().await;
+
+    let Relabeled { changed, mut failed } = labels.relabel(repository, from, to).await?;
+
+    if !changed.is_empty() {
+        evaluator.invalidate();
+    }
+
+    for bundle in &changed {
+        if let Err(err) = index.index(bundle).await {
+            failed.push((*bundle.id(), err));
+        }
+    }
+
+    // The classifiers can not unlearn single documents, so the suggester is trained from scratch
+    if !changed.is_empty() {
+        retrain(suggester, repository).await?;
+    }
+
+    if !failed.is_empty() {
+        let failed = failed.iter()
+            .map(|(id, err)| format!("{}: {:#}", id, err))
+            .collect::<Vec<_>>();
+        return Err(anyhow!("Failed to relabel {} documents: {}", failed.len(), failed.join(", ")).into());
+    }
+
+    return Ok(ChangeResponse {
+        documents: changed.len(),
+    });
+}
+
+fn is_color(color: &str) -> bool {
+    return color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+}
+
+/// Maximum number of folds for the cross-validation.
+const MAX_FOLDS: usize = 20;
+
+#[get("/labels/stats?<folds>")]
+pub(super) async fn stats(folds: Option<usize>,
+                          repository: State<'_, Repository>,
+                          evaluator: State<'_, Evaluator>,
+                          token: &'_ Token) -> Result<Json<StatsResponse>, ApiError> {
+    require(token, Scope::Read)?;
+
+    let folds = folds.unwrap_or(DEFAULT_FOLDS);
+    if !(2..=MAX_FOLDS).contains(&folds) {
+        return Err(ApiError::bad_request(format!("Number of folds must be between 2 and {}", MAX_FOLDS)));
+    }
+
+    let evaluation = evaluator.evaluate(repository.inner(), folds).await?;
+    let mut feedback = feedback(repository.inner()).await?;
+
+    let mut labels = evaluation.labels.into_iter()
+        .map(|(label, confusion)| {
+            let feedback = feedback.remove(&label).unwrap_or_default();
+            return (label, LabelStats {
+                precision: confusion.precision(),
+                recall: confusion.recall(),
+                support: confusion.support(),
+                accepted: feedback.accepted,
+                rejected: feedback.rejected,
+            });
+        })
+        .collect::<HashMap<_, _>>();
+
+    // Labels which have been suggested at archive time but are not known to the evaluation
+    labels.extend(feedback.into_iter()
+        .map(|(label, feedback)| (label, LabelStats {
+            precision: None,
+            recall: None,
+            support: 0,
+            accepted: feedback.accepted,
+            rejected: feedback.rejected,
+        })));
+
+    return Ok(Json(StatsResponse {
+        documents: evaluation.documents,
+        labels,
+    }));
 }
 
 // #[get("/labels/guess/<id>")]
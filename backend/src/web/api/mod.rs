@@ -1,7 +1,7 @@
 use rocket::{Route, routes};
 
 pub(super) use auth::Authorization;
-pub(self) use auth::Token;
+pub(self) use auth::{require, Token};
 pub(self) use error::{ApiError, InternalError};
 
 pub(self) mod auth;
@@ -11,19 +11,43 @@ mod upload;
 mod inbox;
 mod archive;
 mod labels;
+mod audit;
+mod keys;
 
 pub fn routes() -> Vec<Route> {
     routes![
         auth::login,
+        auth::refresh,
+        auth::logout,
         upload::upload_pdf,
+        upload::upload_jpeg,
+        upload::upload_png,
+        upload::upload_tiff,
+        upload::upload_multipart,
         inbox::list,
         inbox::bundle,
         inbox::fragment,
+        inbox::preview,
+        inbox::thumbnail,
         inbox::delete,
         inbox::archive,
+        inbox::split,
+        inbox::merge,
+        inbox::pages,
         archive::bundle,
         archive::fragment,
+        archive::preview,
+        archive::thumbnail,
         archive::search,
         labels::list,
+        labels::describe,
+        labels::rename,
+        labels::merge,
+        labels::delete,
+        labels::stats,
+        audit::list,
+        keys::list,
+        keys::create,
+        keys::revoke,
     ]
 }
@@ -1,33 +1,124 @@
 use anyhow::Context;
+use bytes::BytesMut;
+use futures::TryStreamExt;
 use log::{info, trace};
+use multer::Multipart;
 use rocket::{Data, post, State};
 use rocket::data::ToByteUnit;
+use rocket::http::ContentType;
 use rocket_contrib::json::Json;
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{BytesCodec, FramedRead};
 
-use crate::juicer::Juicer;
+use crate::audit::{Action, Audit};
+use crate::juicer::{Assembly, Juicer, juice, Part};
 use crate::meta::Metadata;
 use crate::proto::api::upload::UploadResponse;
-use crate::proto::model::{DocInfo, Kind};
-use crate::repository::Repository;
+use crate::proto::model::{DocInfo, Kind, Scope};
+use crate::repository::{Bundle, Repository, Staging};
 
-use super::{ApiError, Token};
+use super::{ApiError, require, Token};
+
+enum Upload {
+    /// A single document stored as original fragment with the given extension.
+    Single { data: Data, extension: &'static str },
+
+    /// Multiple documents uploaded as multipart request which are merged into a single document.
+    ///
+    /// If `reverse` is set, the pages of the last document are taken in reverse order to merge back-sides scanned in
+    /// reverse order.
+    Merged { data: Data, boundary: String, interleave: bool, reverse: bool },
+}
 
 #[post("/upload", format = "application/pdf", data = "<data>")]
 pub(super) async fn upload_pdf(data: Data,
                                repository: State<'_, Repository>,
                                juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
-                               _token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+                               audit: State<'_, Audit>,
+                               token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+    require(token, Scope::Upload)?;
+
+    return upload(Upload::Single { data, extension: "pdf" }, repository.inner(), juicer.inner().as_ref(), audit.inner(), token).await;
+}
+
+#[post("/upload", format = "image/jpeg", data = "<data>")]
+pub(super) async fn upload_jpeg(data: Data,
+                                repository: State<'_, Repository>,
+                                juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                                audit: State<'_, Audit>,
+                                token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+    require(token, Scope::Upload)?;
+
+    return upload(Upload::Single { data, extension: "jpg" }, repository.inner(), juicer.inner().as_ref(), audit.inner(), token).await;
+}
+
+#[post("/upload", format = "image/png", data = "<data>")]
+pub(super) async fn upload_png(data: Data,
+                               repository: State<'_, Repository>,
+                               juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                               audit: State<'_, Audit>,
+                               token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+    require(token, Scope::Upload)?;
+
+    return upload(Upload::Single { data, extension: "png" }, repository.inner(), juicer.inner().as_ref(), audit.inner(), token).await;
+}
+
+#[post("/upload", format = "image/tiff", data = "<data>")]
+pub(super) async fn upload_tiff(data: Data,
+                                repository: State<'_, Repository>,
+                                juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                                audit: State<'_, Audit>,
+                                token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+    require(token, Scope::Upload)?;
+
+    return upload(Upload::Single { data, extension: "tiff" }, repository.inner(), juicer.inner().as_ref(), audit.inner(), token).await;
+}
+
+#[post("/upload?<interleave>&<reverse>", format = "multipart/form-data", data = "<data>")]
+pub(super) async fn upload_multipart(data: Data,
+                                     interleave: Option<bool>,
+                                     reverse: Option<bool>,
+                                     content_type: &ContentType,
+                                     repository: State<'_, Repository>,
+                                     juicer: State<'_, Box<dyn Juicer + Send + Sync>>,
+                                     audit: State<'_, Audit>,
+                                     token: &'_ Token) -> Result<Json<UploadResponse>, ApiError> {
+    require(token, Scope::Upload)?;
+
+    let boundary = multer::parse_boundary(content_type.to_string())
+        .map_err(|err| ApiError::bad_request(format!("Invalid multipart request: {}", err)))?;
+
+    return upload(Upload::Merged { data, boundary, interleave: interleave.unwrap_or(false), reverse: reverse.unwrap_or(false) },
+                  repository.inner(), juicer.inner().as_ref(), audit.inner(), token).await;
+}
+
+/// Maps the content type of an uploaded document to the extension of the original fragment.
+fn extension(content_type: &str) -> Option<&'static str> {
+    return match content_type {
+        "application/pdf" => Some("pdf"),
+        "image/jpeg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/tiff" => Some("tiff"),
+        _ => None,
+    };
+}
+
+async fn upload(upload: Upload,
+                repository: &Repository,
+                juicer: &(dyn Juicer + Send + Sync),
+                audit: &Audit,
+                token: &Token) -> Result<Json<UploadResponse>, ApiError> {
     // Create a new staging area
     let staging = repository.stage().await?;
 
     info!("Uploading to staging bundle {}", staging.id());
 
     match (|| async {
-        // Write the uploaded file to the staging area
-        let original_fragment = staging.write(Kind::other("original.pdf")).await?;
-        data.open(512.mebibytes()) // TODO: Make this limit configurable
-            .stream_to(original_fragment).await
-            .context("Writing original.pdf to staging")?;
+        // Write the uploaded files to the staging area
+        match upload {
+            Upload::Single { data, extension } => store_single(&staging, data, extension).await?,
+            Upload::Merged { data, boundary, interleave, reverse } => store_merged(&staging, data, boundary, interleave, reverse).await?,
+        }
 
         trace!("Original fragment written");
 
@@ -38,7 +129,7 @@ pub(super) async fn upload_pdf(data: Data,
         trace!("Metadata fragment written");
 
         // Run the juicer over this upload
-        juicer.extract(&staging).await?;
+        juice(juicer, &staging).await?;
 
         trace!("Juicer finished");
 
@@ -49,6 +140,8 @@ pub(super) async fn upload_pdf(data: Data,
             let bundle = staging.create().await?;
             let metadata = bundle.read_metadata().await?;
 
+            audit.report(token.username(), Action::Upload { doc: *bundle.id() }).await;
+
             return Ok(Json(UploadResponse {
                 doc: DocInfo {
                     id: *bundle.id(),
@@ -64,3 +157,60 @@ pub(super) async fn upload_pdf(data: Data,
         }
     }
 }
+
+async fn store_single(staging: &Bundle<'_, Staging>,
+                      data: Data,
+                      extension: &str) -> Result<(), ApiError> {
+    let original = format!("original.{}", extension);
+
+    let original_fragment = staging.write(Kind::other(&original)).await?;
+    data.open(512.mebibytes()) // TODO: Make this limit configurable
+        .stream_to(original_fragment).await
+        .with_context(|| format!("Writing {} to staging", original))?;
+
+    return Ok(());
+}
+
+async fn store_merged(staging: &Bundle<'_, Staging>,
+                      data: Data,
+                      boundary: String,
+                      interleave: bool,
+                      reverse: bool) -> Result<(), ApiError> {
+    let stream = FramedRead::new(data.open(512.mebibytes()), BytesCodec::new()) // TODO: Make this limit configurable
+        .map_ok(BytesMut::freeze);
+
+    let mut multipart = Multipart::new(stream, boundary);
+
+    // Store each part as separate original fragment
+    let mut parts = Vec::new();
+    while let Some(mut field) = multipart.next_field().await.context("Reading multipart upload")? {
+        let extension = field.content_type()
+            .and_then(|content_type| extension(content_type.essence_str()))
+            .ok_or_else(|| ApiError::bad_request(format!("Unsupported content type of part {}", parts.len() + 1)))?;
+
+        let original = format!("original-{}.{}", parts.len() + 1, extension);
+
+        let mut original_fragment = staging.write(Kind::other(&original)).await?;
+        while let Some(chunk) = field.chunk().await.context("Reading multipart upload")? {
+            original_fragment.write_all(&chunk).await
+                .with_context(|| format!("Writing {} to staging", original))?;
+        }
+
+        parts.push(Part { source: original, pages: None, rotate: None });
+    }
+
+    if parts.is_empty() {
+        return Err(ApiError::bad_request(String::from("No documents in multipart upload")));
+    }
+
+    if reverse {
+        if let Some(last) = parts.last_mut() {
+            last.pages = Some(String::from("z-1"));
+        }
+    }
+
+    // Instruct the juicer to merge the parts into the original document
+    Assembly { interleave, oriented: false, parts }.save(staging).await?;
+
+    return Ok(());
+}
@@ -1,11 +1,18 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 
+use crate::audit::Audit;
 use crate::auth::Authenticator;
 use crate::config::Web as Config;
+use crate::extractor::Extractor;
 use crate::index::Index;
 use crate::juicer::Juicer;
+use crate::labels::Labels;
 use crate::repository::Repository;
-use crate::suggester::Suggester;
+use crate::schema::Schema;
+use crate::suggester::evaluation::Evaluator;
+use crate::suggester::{Suggester, Training};
 
 mod api;
 mod frontend;
@@ -18,7 +25,12 @@ pub fn server(config: Config,
               repository: Repository,
               index: Box<dyn Index + Send + Sync>,
               juicer: Box<dyn Juicer + Send + Sync>,
-              suggester: Box<dyn Suggester + Send + Sync>) -> Result<rocket::Rocket> {
+              suggester: Arc<dyn Suggester + Send + Sync>,
+              extractor: Extractor,
+              evaluator: Evaluator,
+              labels: Arc<Labels>,
+              schema: Schema,
+              audit: Audit) -> Result<rocket::Rocket> {
     let figment = rocket::figment::Figment::from(rocket::Config::default())
         .merge(("address", config.address))
         .merge(("port", config.port));
@@ -30,6 +42,12 @@ pub fn server(config: Config,
         .manage(index)
         .manage(juicer)
         .manage(suggester)
+        .manage(Training::default())
+        .manage(extractor)
+        .manage(evaluator)
+        .manage(labels)
+        .manage(schema)
+        .manage(audit)
         .mount("/api", api::routes())
         .mount("/", frontend::Frontend {}))
 }
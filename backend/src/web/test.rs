@@ -9,44 +9,94 @@ struct Server {
     pub index: crate::index::MockIndex,
     pub juicer: crate::juicer::MockJuicer,
     pub suggester: crate::suggester::MockSuggester,
+    pub extractor: crate::extractor::Extractor,
+    pub evaluator: crate::suggester::evaluation::Evaluator,
+    pub schema: crate::schema::Schema,
 }
 
 impl Server {
     pub async fn new() -> Self {
+        let repository = crate::repository::Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+
         let mut api_keys = HashMap::new();
         api_keys.insert(String::from("test"), String::from("$2y$12$8X8eghlzYFEKYhcOdqIU6OqaC5oACEYpfLzJChXPPIHBO6aRmzXaC")); // "testkey"
 
+        let mut users = HashMap::new();
+        users.insert(String::from("other"), crate::config::User::Passhash(String::from("$2y$12$8X8eghlzYFEKYhcOdqIU6OqaC5oACEYpfLzJChXPPIHBO6aRmzXaC"))); // "testkey"
+        users.insert(String::from("reader"), crate::config::User::Restricted {
+            passhash: String::from("$2y$12$8X8eghlzYFEKYhcOdqIU6OqaC5oACEYpfLzJChXPPIHBO6aRmzXaC"), // "testkey"
+            scopes: vec![crate::proto::model::Scope::Read].into_iter().collect(),
+        });
+
         let authenticator = crate::auth::Authenticator::from_config(crate::config::Auth {
-            passhash: "$2y$12$/luV8edFPQFt7Vc3O9MgReHsFoQUD0Vu4g9nkjFb/fK0ib3HwJ9/G".to_string(), // "pass"
+            username: Some(String::from("user")),
+            passhash: Some("$2y$12$/luV8edFPQFt7Vc3O9MgReHsFoQUD0Vu4g9nkjFb/fK0ib3HwJ9/G".to_string()), // "pass"
+            users,
+            users_file: None,
             secret: "my dirty secret".to_string(),
+            session: crate::config::Session::default(),
             api_keys,
-        }).await.unwrap();
-
-        let repository = crate::repository::Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+        },
+            std::sync::Arc::new(crate::keys::Keys::open(&repository).await.unwrap()),
+            crate::revocations::Revocations::open(&repository).await.unwrap(),
+        ).await.unwrap();
 
         let index = crate::index::MockIndex::new();
         let juicer = crate::juicer::MockJuicer::new();
         let suggester = crate::suggester::MockSuggester::new();
 
+        let mut properties = HashMap::new();
+        properties.insert(String::from("subject"), vec![String::from(r"my (\w+) plaintext")]);
+
+        let extractor = crate::extractor::Extractor::from_config(crate::config::Extractor {
+            properties,
+        }).unwrap();
+
+        let evaluator = crate::suggester::evaluation::Evaluator::from_config(crate::config::Suggester::Dumb(crate::config::DumbSuggester {
+            path: None,
+        }));
+
+        let mut definitions = HashMap::new();
+        definitions.insert(String::from("amount"), crate::config::Property {
+            kind: crate::config::PropertyType::Money,
+            required: vec![String::from("Finance/Invoice")],
+        });
+
+        let schema = crate::schema::Schema::from_config(crate::config::Properties {
+            strict: false,
+            definitions,
+        }).unwrap();
+
         return Server {
             authenticator,
             repository,
             index,
             juicer,
             suggester,
+            extractor,
+            evaluator,
+            schema,
         };
     }
 
     pub async fn client(self) -> rocket::local::asynchronous::Client {
         let config = crate::config::Web { address: "127.0.0.1".to_string(), port: 0 };
 
+        let labels = std::sync::Arc::new(crate::labels::Labels::open(&self.repository).await.unwrap());
+        let audit = crate::audit::Audit::open(&self.repository).await.unwrap();
+
         let rocket = crate::web::server(
             config,
             self.authenticator,
             self.repository,
             Box::new(self.index),
             Box::new(self.juicer),
-            Box::new(self.suggester),
+            std::sync::Arc::new(self.suggester),
+            self.extractor,
+            self.evaluator,
+            labels,
+            self.schema,
+            audit,
         ).unwrap();
 
         return rocket::local::asynchronous::Client::untracked(rocket).await.unwrap();
@@ -108,6 +158,7 @@ mod api {
             let response = client.post("/api/auth/login")
                 .header(ContentType::JSON)
                 .body(json_payload!({
+                    "username": "user",
                     "password": "pass",
                 }))
                 .dispatch().await;
@@ -117,261 +168,589 @@ mod api {
         }
 
         #[tokio::test]
-        async fn test_login_failure() {
+        async fn test_login_other_user() {
             let server = Server::new().await;
             let client = server.client().await;
 
             let response = client.post("/api/auth/login")
                 .header(ContentType::JSON)
                 .body(json_payload!({
-                    "password": "wrong pass",
+                    "username": "other",
+                    "password": "testkey",
                 }))
                 .dispatch().await;
 
-            assert_that!(response.status()).is_equal_to(Status::BadRequest);
-            assert_that!(response.headers().get_one("Authorization")).is_none();
-        }
-    }
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-    fn api_key() -> impl Into<Header<'static>> {
-        let basic = format!("{}:{}", "test", "testkey");
-        let basic = base64::encode(basic);
+            // The password of one user is not valid for another
+            let response = client.post("/api/auth/login")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "username": "user",
+                    "password": "testkey",
+                }))
+                .dispatch().await;
 
-        return Header::new("Authorization", format!("Basic {}", basic));
-    }
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
 
-    mod upload {
-        use mockall::predicate;
-        use rand::RngCore;
-        use rand::rngs::OsRng;
+            // The username is required if there are multiple accounts
+            let response = client.post("/api/auth/login")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "password": "testkey",
+                }))
+                .dispatch().await;
 
-        use super::*;
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+        }
 
         #[tokio::test]
-        async fn test_upload() {
-            let mut server = Server::new().await;
-
-            server.juicer.expect_extract()
-                .with(predicate::always())
-                .times(1)
-                .return_once(|_| Ok(()));
-
+        async fn test_login_scopes() {
+            let server = Server::new().await;
             let client = server.client().await;
 
-            // Create the document from random data
-            let mut doc = [0u8; 1024];
-            OsRng.fill_bytes(&mut doc);
-
-            let response = client.post("/api/upload")
-                .header(ContentType::PDF)
-                .header(api_key())
-                .body(doc)
-                .dispatch().await;
+            async fn login<'c>(client: &'c rocket::local::asynchronous::Client,
+                               username: &str,
+                               password: &str,
+                               scopes: serde_json::Value) -> rocket::local::asynchronous::LocalResponse<'c> {
+                return client.post("/api/auth/login")
+                    .header(ContentType::JSON)
+                    .body(json_payload!({
+                        "username": username,
+                        "password": password,
+                        "scopes": scopes,
+                    }))
+                    .dispatch().await;
+            }
+
+            // A session restricted to reading
+            let response = login(&client, "user", "pass", serde_json::json!(["read"])).await;
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
+            let bearer = Header::new("Authorization", format!("Bearer {}", response.headers().get_one("Authorization").unwrap()));
 
+            let response = client.get("/api/labels")
+                .header(bearer.clone())
+                .dispatch().await;
             assert_that!(response.status()).is_equal_to(Status::Ok);
-        }
-    }
 
-    mod inbox {
-        use std::collections::HashSet;
-        use std::iter::FromIterator;
+            let response = client.get("/api/inbox")
+                .header(bearer)
+                .dispatch().await;
+            assert_that!(response.status()).is_equal_to(Status::Forbidden);
 
-        use chrono::{DateTime, NaiveDateTime, Utc};
-        use futures::{stream, StreamExt};
-        use serde_json::json;
-        use tokio::io::AsyncWriteExt;
-        use tokio::time::Duration;
+            // A user can not be granted more than its scopes
+            let response = login(&client, "reader", "testkey", serde_json::json!(["review"])).await;
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
 
-        use crate::meta::Metadata;
-        use crate::proto::model::{Kind, Label};
+            let response = login(&client, "reader", "testkey", serde_json::Value::Null).await;
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-        use super::*;
+            let bearer = Header::new("Authorization", format!("Bearer {}", response.headers().get_one("Authorization").unwrap()));
+
+            let response = client.get("/api/keys")
+                .header(bearer)
+                .dispatch().await;
+            assert_that!(response.status()).is_equal_to(Status::Forbidden);
+        }
 
         #[tokio::test]
-        async fn test_list() {
+        async fn test_refresh_and_logout() {
             let server = Server::new().await;
+            let client = server.client().await;
 
-            // Create bundles in inbox (with a short delay between each to have a unique timestamp)
-            let ids = tokio::time::throttle(Duration::from_millis(10),
-                                            stream::iter(0..13usize))
-                .then(|_| async {
-                    let bundle = server.repository.stage().await.unwrap();
+            let response = client.post("/api/auth/login")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "username": "user",
+                    "password": "pass",
+                }))
+                .dispatch().await;
 
-                    Metadata {
-                        uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
-                        ..Metadata::new()
-                    }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-                    let bundle = bundle.create().await.unwrap();
+            let tokens = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let refresh = tokens["refresh"].as_str().unwrap().to_string();
 
-                    *bundle.id()
-                }).collect::<Vec<_>>().await;
+            // Only refresh tokens can be used for refreshing
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "refresh": tokens["access"],
+                }))
+                .dispatch().await;
 
-            let client = server.client().await;
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-            let response = client.get("/api/inbox")
-                .header(api_key())
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "refresh": refresh,
+                }))
                 .dispatch().await;
 
-            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-            assert_json_eq!(response.into_bytes().await.unwrap(), {
-                "count": 13,
-                "docs": ids[0..10].iter().map(|id| json!({
-                    "id": id,
-                    "metadata": {
-                        "archived": (),
-                        "uploaded": "2001-09-09T01:46:40Z",
-                        "pages": 0,
-                        "title": (),
-                        "labels": [],
-                        "properties": {},
-                    }
-                })).collect::<Vec<_>>(),
-            });
-        }
+            let tokens = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let bearer = Header::new("Authorization", format!("Bearer {}", tokens["access"].as_str().unwrap()));
 
-        #[tokio::test]
-        async fn test_get() {
-            let mut server = Server::new().await;
+            // Refresh tokens are not accepted as access tokens
+            let response = client.get("/api/labels")
+                .header(Header::new("Authorization", format!("Bearer {}", refresh)))
+                .dispatch().await;
 
-            let doc_id = {
-                let staging = server.repository.stage().await.unwrap();
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-                staging.write(Kind::Document).await.unwrap()
-                    .write_all(b"").await.unwrap();
+            // Authenticated requests do not renew the token
+            let response = client.get("/api/labels")
+                .header(bearer.clone())
+                .dispatch().await;
 
-                staging.write(Kind::Plaintext).await.unwrap()
-                    .write_all(b"my document plaintext").await.unwrap();
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.headers().get_one("Authorization")).is_none();
 
-                Metadata {
-                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
-                    ..Metadata::new()
-                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+            let response = client.post("/api/auth/logout")
+                .header(bearer.clone())
+                .dispatch().await;
 
-                *staging.create().await.unwrap().id()
-            };
+            assert_that!(response.status()).is_equal_to(Status::Ok);
 
-            server.suggester.expect_guess()
-                .with(mockall::predicate::eq("my document plaintext"))
-                .returning(|_| Ok(HashSet::from_iter(vec![Label::from("suggestion")])));
+            // All tokens of the session are revoked
+            let response = client.get("/api/labels")
+                .header(bearer)
+                .dispatch().await;
 
-            let client = server.client().await;
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-            let response = client.get(format!("/api/inbox/{}", doc_id))
-                .header(api_key())
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "refresh": refresh,
+                }))
                 .dispatch().await;
 
-            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-            assert_json_eq!(response.into_bytes().await.unwrap(), {
-                "id": doc_id.to_string(),
-                "metadata": {
-                    "archived": (),
-                    "uploaded": "2001-09-09T01:46:40Z",
-                    "pages": 0,
-                    "title": (),
-                    "labels": [],
-                    "properties": {},
-                },
-                "suggestions": ["suggestion"],
-            });
+            // API keys have no session to log out
+            let response = client.post("/api/auth/logout")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
         }
 
         #[tokio::test]
-        async fn test_delete() {
+        async fn test_login_failure() {
             let server = Server::new().await;
+            let client = server.client().await;
 
-            let doc_id = {
-                let staging = server.repository.stage().await.unwrap();
-
-                staging.write(Kind::Document).await.unwrap()
-                    .write_all(b"").await.unwrap();
-
-                staging.write(Kind::Plaintext).await.unwrap()
-                    .write_all(b"my document plaintext").await.unwrap();
-
-                Metadata {
-                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
-                    ..Metadata::new()
-                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+            let response = client.post("/api/auth/login")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "username": "user",
+                    "password": "wrong pass",
+                }))
+                .dispatch().await;
 
-                *staging.create().await.unwrap().id()
-            };
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+            assert_that!(response.headers().get_one("Authorization")).is_none();
+        }
 
+        #[tokio::test]
+        async fn test_refresh_once() {
+            let server = Server::new().await;
             let client = server.client().await;
 
-            let response = client.delete(format!("/api/inbox/{}", doc_id))
-                .header(api_key())
+            let response = client.post("/api/auth/login")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "username": "user",
+                    "password": "pass",
+                }))
                 .dispatch().await;
 
-            assert_that!(response.status()).is_equal_to(Status::Ok);
-        }
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-        #[tokio::test]
-        async fn test_archive() {
-            let mut server = Server::new().await;
+            let tokens = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
 
-            let doc_id = {
-                let staging = server.repository.stage().await.unwrap();
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "refresh": tokens["refresh"],
+                }))
+                .dispatch().await;
 
-                staging.write(Kind::Document).await.unwrap()
-                    .write_all(b"").await.unwrap();
+            assert_that!(response.status()).is_equal_to(Status::Accepted);
 
-                staging.write(Kind::Plaintext).await.unwrap()
-                    .write_all(b"my document plaintext").await.unwrap();
+            let refreshed = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let bearer = Header::new("Authorization", format!("Bearer {}", refreshed["access"].as_str().unwrap()));
 
-                Metadata {
-                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
-                    ..Metadata::new()
-                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+            // Each refresh token is rotated
+            assert_that!(refreshed["refresh"]).is_not_equal_to(&tokens["refresh"]);
 
-                *staging.create().await.unwrap().id()
-            };
+            // Using a refresh token again revokes the session
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
+                .body(json_payload!({
+                    "refresh": tokens["refresh"],
+                }))
+                .dispatch().await;
 
-            server.index.expect_index()
-                .withf(move |bundle| bundle.id() == &doc_id)
-                .returning(|_| Ok(()));
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-            server.suggester.expect_train()
-                .with(mockall::predicate::eq("my document plaintext"),
-                      mockall::predicate::eq(HashSet::from_iter(vec![Label::from("expected")])))
-                .returning(|_, _| Ok(()));
+            let response = client.get("/api/labels")
+                .header(bearer)
+                .dispatch().await;
 
-            let client = server.client().await;
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
 
-            let response = client.post(format!("/api/inbox/{}", doc_id))
-                .header(api_key())
+            let response = client.post("/api/auth/refresh")
+                .header(ContentType::JSON)
                 .body(json_payload!({
-                    "labels": [ "expected" ],
-                    "properties": {
-                        "title": "My little Test",
-                        "source": "testsuite",
-                    }
+                    "refresh": refreshed["refresh"],
                 }))
                 .dispatch().await;
 
-            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
         }
-    }
 
-    mod archive {
-        use chrono::{DateTime, NaiveDateTime, Utc};
+        /// Creates an authenticator for the given users sharing the sessions stored in the repository.
+        async fn authenticator(repository: &crate::repository::Repository,
+                               users: HashMap<String, crate::config::User>,
+                               legacy: bool) -> anyhow::Result<crate::auth::Authenticator> {
+            return crate::auth::Authenticator::from_config(crate::config::Auth {
+                username: legacy.then(|| String::from("user")),
+                passhash: legacy.then(|| "$2y$12$/luV8edFPQFt7Vc3O9MgReHsFoQUD0Vu4g9nkjFb/fK0ib3HwJ9/G".to_string()), // "pass"
+                users,
+                users_file: None,
+                secret: "my dirty secret".to_string(),
+                session: crate::config::Session::default(),
+                api_keys: HashMap::new(),
+            },
+                std::sync::Arc::new(crate::keys::Keys::open(repository).await.unwrap()),
+                crate::revocations::Revocations::open(repository).await.unwrap(),
+            ).await;
+        }
+
+        #[tokio::test]
+        async fn test_refresh_changed_account() {
+            let repository = crate::repository::Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+
+            let mut users = HashMap::new();
+            users.insert(String::from("other"), crate::config::User::Passhash(String::from("$2y$12$8X8eghlzYFEKYhcOdqIU6OqaC5oACEYpfLzJChXPPIHBO6aRmzXaC"))); // "testkey"
+
+            let before = authenticator(&repository, users, true).await.unwrap();
+
+            let user = before.login(Some("user"), "pass", None).await.unwrap();
+            let user = before.issue(&user).await.unwrap();
+
+            let other = before.login(Some("other"), "testkey", None).await.unwrap();
+            let other = before.issue(&other).await.unwrap();
+
+            // Restrict the scopes of one user and remove the other one
+            let mut users = HashMap::new();
+            users.insert(String::from("user"), crate::config::User::Restricted {
+                passhash: String::from("$2y$12$/luV8edFPQFt7Vc3O9MgReHsFoQUD0Vu4g9nkjFb/fK0ib3HwJ9/G"), // "pass"
+                scopes: vec![crate::proto::model::Scope::Read].into_iter().collect(),
+            });
+
+            let after = authenticator(&repository, users, false).await.unwrap();
+
+            let token = after.refresh(&user.refresh).await.unwrap();
+            assert_that!(token.scopes().iter().copied().collect::<Vec<_>>()).is_equal_to(vec![crate::proto::model::Scope::Read]);
+
+            assert!(after.refresh(&other.refresh).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_duplicate_user() {
+            let repository = crate::repository::Repository::with_path(tempfile::tempdir().unwrap()).await.unwrap();
+
+            let mut users = HashMap::new();
+            users.insert(String::from("user"), crate::config::User::Passhash(String::from("$2y$12$8X8eghlzYFEKYhcOdqIU6OqaC5oACEYpfLzJChXPPIHBO6aRmzXaC"))); // "testkey"
+
+            // The legacy account must not replace the configured user of the same name
+            assert!(authenticator(&repository, users, true).await.is_err());
+        }
+    }
+
+    fn api_key() -> impl Into<Header<'static>> {
+        let basic = format!("{}:{}", "test", "testkey");
+        let basic = base64::encode(basic);
+
+        return Header::new("Authorization", format!("Basic {}", basic));
+    }
+
+    /// Simulates a successful juicer run by writing all required fragments for a single page document.
+    fn juice(bundle: &crate::repository::Bundle<'_, crate::repository::Staging>) -> anyhow::Result<()> {
+        use crate::meta::Metadata;
+        use crate::proto::model::Kind;
+
+        std::fs::write(bundle.path_of(Kind::Document), b"")?;
+        std::fs::write(bundle.path_of(Kind::Plaintext), b"my document plaintext")?;
+        std::fs::write(bundle.path_of(Kind::Preview), b"")?;
+        std::fs::write(bundle.path_of(Kind::page_preview(1)), b"")?;
+        std::fs::write(bundle.path_of(Kind::thumbnail(1)), b"")?;
+
+        // Keep the existing metadata like the juicer does
+        let metadata = serde_json::from_slice::<Metadata>(&std::fs::read(bundle.path_of(Kind::Metadata))?)?;
+        std::fs::write(bundle.path_of(Kind::Metadata), Metadata {
+            pages: 1,
+            ..metadata
+        }.to_vec()?)?;
+
+        return Ok(());
+    }
+
+    mod upload {
+        use mockall::predicate;
+        use rand::RngCore;
+        use rand::rngs::OsRng;
+
+        use crate::proto::model::Kind;
+
+        use super::*;
+
+        #[tokio::test]
+        async fn test_upload() {
+            let mut server = Server::new().await;
+
+            server.juicer.expect_extract()
+                .with(predicate::always())
+                .times(1)
+                .return_once(|bundle| juice(bundle));
+
+            let client = server.client().await;
+
+            // Create the document from random data
+            let mut doc = [0u8; 1024];
+            OsRng.fill_bytes(&mut doc);
+
+            let response = client.post("/api/upload")
+                .header(ContentType::PDF)
+                .header(api_key())
+                .body(doc)
+                .dispatch().await;
+
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_upload_image() {
+            let mut server = Server::new().await;
+
+            server.juicer.expect_extract()
+                .withf(|bundle| bundle.path_of(Kind::other("original.png")).is_file())
+                .times(1)
+                .return_once(|bundle| juice(bundle));
+
+            let client = server.client().await;
+
+            // Create the document from random data
+            let mut doc = [0u8; 1024];
+            OsRng.fill_bytes(&mut doc);
+
+            let response = client.post("/api/upload")
+                .header(ContentType::PNG)
+                .header(api_key())
+                .body(doc)
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_upload_merged() {
+            let mut server = Server::new().await;
+
+            server.juicer.expect_extract()
+                .withf(|bundle| {
+                    let assembly = std::fs::read(bundle.path_of(Kind::other("assemble.json"))).unwrap();
+                    let assembly = serde_json::from_slice::<serde_json::Value>(&assembly).unwrap();
+
+                    return bundle.path_of(Kind::other("original-1.pdf")).is_file()
+                        && bundle.path_of(Kind::other("original-2.png")).is_file()
+                        && assembly == serde_json::json!({
+                            "interleave": true,
+                            "parts": [
+                                { "source": "original-1.pdf" },
+                                { "source": "original-2.png" },
+                            ],
+                        });
+                })
+                .times(1)
+                .return_once(|bundle| juice(bundle));
+
+            let client = server.client().await;
+
+            let body = [
+                "--boundary\r\n",
+                "Content-Disposition: form-data; name=\"document\"\r\n",
+                "Content-Type: application/pdf\r\n",
+                "\r\n",
+                "front pages\r\n",
+                "--boundary\r\n",
+                "Content-Disposition: form-data; name=\"document\"\r\n",
+                "Content-Type: image/png\r\n",
+                "\r\n",
+                "back pages\r\n",
+                "--boundary--\r\n",
+            ].concat();
+
+            let response = client.post("/api/upload?interleave=true")
+                .header(Header::new("Content-Type", "multipart/form-data; boundary=boundary"))
+                .header(api_key())
+                .body(body)
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_upload_merged_reversed() {
+            let mut server = Server::new().await;
+
+            server.juicer.expect_extract()
+                .withf(|bundle| {
+                    let assembly = std::fs::read(bundle.path_of(Kind::other("assemble.json"))).unwrap();
+                    let assembly = serde_json::from_slice::<serde_json::Value>(&assembly).unwrap();
+
+                    return assembly == serde_json::json!({
+                        "interleave": true,
+                        "parts": [
+                            { "source": "original-1.pdf" },
+                            { "source": "original-2.pdf", "pages": "z-1" },
+                        ],
+                    });
+                })
+                .times(1)
+                .return_once(|bundle| juice(bundle));
+
+            let client = server.client().await;
+
+            let body = [
+                "--boundary\r\n",
+                "Content-Disposition: form-data; name=\"document\"\r\n",
+                "Content-Type: application/pdf\r\n",
+                "\r\n",
+                "front pages\r\n",
+                "--boundary\r\n",
+                "Content-Disposition: form-data; name=\"document\"\r\n",
+                "Content-Type: application/pdf\r\n",
+                "\r\n",
+                "back pages in reverse order\r\n",
+                "--boundary--\r\n",
+            ].concat();
+
+            let response = client.post("/api/upload?interleave=true&reverse=true")
+                .header(Header::new("Content-Type", "multipart/form-data; boundary=boundary"))
+                .header(api_key())
+                .body(body)
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_upload_invalid() {
+            let mut server = Server::new().await;
+
+            server.juicer.expect_extract()
+                .with(predicate::always())
+                .times(1)
+                .return_once(|_| Ok(()));
+
+            let client = server.client().await;
+
+            // Create the document from random data
+            let mut doc = [0u8; 1024];
+            OsRng.fill_bytes(&mut doc);
+
+            let response = client.post("/api/upload")
+                .header(ContentType::PDF)
+                .header(api_key())
+                .body(doc)
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::UnprocessableEntity);
+            assert_that!(response.into_string().await.unwrap()).contains("Fragment missing in juicer output");
+
+            let response = client.get("/api/inbox")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "count": 0,
+                "docs": [],
+            });
+        }
+    }
+
+    mod inbox {
+        use std::collections::HashSet;
+        use std::iter::FromIterator;
+
+        use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
         use futures::{stream, StreamExt};
         use serde_json::json;
         use tokio::io::AsyncWriteExt;
+        use tokio::time::Duration;
 
-        use crate::index::SearchResponse;
         use crate::meta::Metadata;
-        use crate::proto::model::Kind;
+        use crate::proto::model::{Kind, Label, Suggestion};
 
         use super::*;
 
         #[tokio::test]
-        async fn test_get_bundle() {
+        async fn test_list() {
             let server = Server::new().await;
 
+            // Create bundles in inbox (with a short delay between each to have a unique timestamp)
+            let ids = tokio::time::throttle(Duration::from_millis(10),
+                                            stream::iter(0..13usize))
+                .then(|_| async {
+                    let bundle = server.repository.stage().await.unwrap();
+
+                    Metadata {
+                        uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                        ..Metadata::new()
+                    }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                    let bundle = bundle.create().await.unwrap();
+
+                    *bundle.id()
+                }).collect::<Vec<_>>().await;
+
+            let client = server.client().await;
+
+            let response = client.get("/api/inbox")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "count": 13,
+                "docs": ids[0..10].iter().map(|id| json!({
+                    "id": id,
+                    "metadata": {
+                        "archived": (),
+                        "uploaded": "2001-09-09T01:46:40Z",
+                        "pages": 0,
+                        "title": (),
+                        "date": (),
+                        "labels": [],
+                        "properties": {},
+                    }
+                })).collect::<Vec<_>>(),
+            });
+        }
+
+        #[tokio::test]
+        async fn test_get() {
+            let mut server = Server::new().await;
+
             let doc_id = {
                 let staging = server.repository.stage().await.unwrap();
 
@@ -386,13 +765,25 @@ mod api {
                     ..Metadata::new()
                 }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
 
-                let inboxed = staging.create().await.unwrap();
-                *inboxed.archive().await.unwrap().id()
+                *staging.create().await.unwrap().id()
             };
 
+            server.suggester.expect_guess()
+                .with(mockall::predicate::eq("my document plaintext"), mockall::predicate::eq(None))
+                .returning(|_, _| Ok(vec![
+                    Suggestion { label: Label::from("suggestion"), score: 0.75 },
+                    Suggestion { label: Label::from("other"), score: 0.5 },
+                ]));
+
+            server.suggester.expect_guess()
+                .with(mockall::predicate::eq("my document plaintext"), mockall::predicate::eq(Some(0.6)))
+                .returning(|_, _| Ok(vec![
+                    Suggestion { label: Label::from("suggestion"), score: 0.75 },
+                ]));
+
             let client = server.client().await;
 
-            let response = client.get(format!("/api/archive/{}", doc_id))
+            let response = client.get(format!("/api/inbox/{}", doc_id))
                 .header(api_key())
                 .dispatch().await;
 
@@ -401,73 +792,733 @@ mod api {
             assert_json_eq!(response.into_bytes().await.unwrap(), {
                 "id": doc_id.to_string(),
                 "metadata": {
-                    "uploaded": "2001-09-09T01:46:40Z",
                     "archived": (),
-                    "title": (),
+                    "uploaded": "2001-09-09T01:46:40Z",
                     "pages": 0,
+                    "title": (),
+                    "date": (),
                     "labels": [],
                     "properties": {},
-                }
+                },
+                "suggestions": [
+                    { "label": "suggestion", "score": 0.75 },
+                    { "label": "other", "score": 0.5 },
+                ],
+                "date_suggestions": [],
+                "property_suggestions": {
+                    "subject": ["document"],
+                },
+                "title_suggestion": "my document plaintext",
             });
+
+            let response = client.get(format!("/api/inbox/{}?certainty=0.6", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            let bundle = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(bundle["suggestions"]).is_equal_to(json!([
+                { "label": "suggestion", "score": 0.75 },
+            ]));
         }
 
         #[tokio::test]
-        async fn test_get_fragment() {
+        async fn test_get_preview() {
             let server = Server::new().await;
 
             let doc_id = {
                 let staging = server.repository.stage().await.unwrap();
 
-                staging.write(Kind::Document).await.unwrap()
-                    .write_all(b"").await.unwrap();
-
-                staging.write(Kind::Plaintext).await.unwrap()
-                    .write_all(b"my document plaintext").await.unwrap();
+                staging.write(Kind::page_preview(2)).await.unwrap()
+                    .write_all(b"my second page").await.unwrap();
 
                 Metadata {
-                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    pages: 2,
                     ..Metadata::new()
                 }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
 
-                let inboxed = staging.create().await.unwrap();
-                *inboxed.archive().await.unwrap().id()
+                *staging.create().await.unwrap().id()
             };
 
             let client = server.client().await;
 
-            let response = client.get(format!("/api/archive/{}/plaintext", doc_id))
+            let response = client.get(format!("/api/inbox/{}/preview/2", doc_id))
                 .header(api_key())
                 .dispatch().await;
 
             assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.content_type()).is_equal_to(Some(ContentType::PNG));
+            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my second page".to_vec());
 
-            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my document plaintext".to_vec());
+            let response = client.get(format!("/api/inbox/{}/preview/3", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+
+            let response = client.get("/api/inbox/00000000-0000-0000-0000-000000000000/preview/1")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
         }
 
         #[tokio::test]
-        async fn test_search() {
-            let mut server = Server::new().await;
+        async fn test_get_thumbnail() {
+            let server = Server::new().await;
 
-            // Create bundles in inbox (with a short delay between each to have a unique timestamp)
-            let ids = stream::iter(0..10usize).then(|_| async {
-                let bundle = server.repository.stage().await.unwrap();
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::thumbnail(1)).await.unwrap()
+                    .write_all(b"my first thumbnail").await.unwrap();
 
                 Metadata {
-                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    pages: 1,
                     ..Metadata::new()
-                }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
 
-                let bundle = bundle.create().await.unwrap();
-                let bundle = bundle.archive().await.unwrap();
+                *staging.create().await.unwrap().id()
+            };
 
-                *bundle.id()
-            }).collect::<Vec<_>>().await;
+            let client = server.client().await;
+
+            let response = client.get(format!("/api/inbox/{}/thumbnail/1", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.content_type()).is_equal_to(Some(ContentType::PNG));
+            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my first thumbnail".to_vec());
+
+            let response = client.get(format!("/api/inbox/{}/thumbnail/2", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_delete() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                staging.write(Kind::Plaintext).await.unwrap()
+                    .write_all(b"my document plaintext").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.delete(format!("/api/inbox/{}", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_archive() {
+            let mut server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                staging.write(Kind::Plaintext).await.unwrap()
+                    .write_all(b"my document plaintext").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            server.index.expect_index()
+                .withf(move |bundle| bundle.id() == &doc_id)
+                .returning(|_| Ok(()));
+
+            server.suggester.expect_guess()
+                .with(mockall::predicate::eq("my document plaintext"), mockall::predicate::eq(None))
+                .returning(|_, _| Ok(vec![
+                    Suggestion { label: Label::from("expected"), score: 0.75 },
+                    Suggestion { label: Label::from("other"), score: 0.5 },
+                ]));
+
+            server.suggester.expect_train()
+                .with(mockall::predicate::eq("my document plaintext"),
+                      mockall::predicate::eq(HashSet::from_iter(vec![Label::from("expected")])))
+                .returning(|_, _| Ok(()));
+
+            let client = server.client().await;
+
+            let response = client.post(format!("/api/inbox/{}", doc_id))
+                .header(api_key())
+                .body(json_payload!({
+                    "title": "My little Test",
+                    "date": "2020-02-29",
+                    "labels": [ "expected" ],
+                    "properties": {
+                        "source": "testsuite",
+                    }
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get(format!("/api/archive/{}", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            let archived = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(archived["metadata"]["title"]).is_equal_to(json!("My little Test"));
+            assert_that!(archived["metadata"]["date"]).is_equal_to(json!("2020-02-29"));
+
+            let response = client.get("/api/labels")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "labels": [
+                    { "label": "expected", "count": 1, "total": 1 },
+                ]
+            });
+
+            let response = client.get("/api/labels/stats")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let stats = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(stats["documents"]).is_equal_to(json!(1));
+            assert_that!(stats["labels"]["expected"]["accepted"]).is_equal_to(json!(1));
+            assert_that!(stats["labels"]["expected"]["rejected"]).is_equal_to(json!(0));
+            assert_that!(stats["labels"]["other"]["accepted"]).is_equal_to(json!(0));
+            assert_that!(stats["labels"]["other"]["rejected"]).is_equal_to(json!(1));
+
+            let response = client.get("/api/audit")
+                .header(api_key())
+                .dispatch().await;
+
+            let audit = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(audit["entries"][0]["user"]).is_equal_to(json!("test"));
+            assert_that!(audit["entries"][0]["action"]).is_equal_to(json!("archive"));
+            assert_that!(audit["entries"][0]["doc"]).is_equal_to(json!(doc_id.to_string()));
+        }
+
+        #[tokio::test]
+        async fn test_archive_invalid() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                Metadata::new().save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            for payload in vec![
+                json_payload!({ "title": "  ", "labels": [], "properties": {} }),
+                json_payload!({ "title": "Line\nbreak", "labels": [], "properties": {} }),
+                json_payload!({ "date": "1020-01-01", "labels": [], "properties": {} }),
+                json_payload!({ "labels": [ "" ], "properties": {} }),
+                json_payload!({ "labels": [], "properties": { "": "value" } }),
+                json_payload!({ "labels": [], "properties": { "amount": "lots" } }),
+                json_payload!({ "labels": [ "Finance/Invoice" ], "properties": {} }),
+            ] {
+                let response = client.post(format!("/api/inbox/{}", doc_id))
+                    .header(api_key())
+                    .body(payload)
+                    .dispatch().await;
+
+                assert_that!(response.status()).is_equal_to(Status::BadRequest);
+            }
+
+            let response = client.get(format!("/api/inbox/{}/metadata", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_split() {
+            let mut server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::other("original.pdf")).await.unwrap()
+                    .write_all(b"my original document").await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    pages: 5,
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            for pages in &["1-2", "3-5"] {
+                let pages = pages.to_string();
+
+                server.juicer.expect_extract()
+                    .withf(move |bundle| {
+                        let assembly = std::fs::read(bundle.path_of(Kind::other("assemble.json"))).unwrap();
+                        let assembly = serde_json::from_slice::<serde_json::Value>(&assembly).unwrap();
+
+                        return std::fs::read(bundle.path_of(Kind::other("source-1.pdf"))).unwrap() == b"my original document"
+                            && assembly == json!({
+                                "interleave": false,
+                                "parts": [
+                                    { "source": "source-1.pdf", "pages": pages },
+                                ],
+                            });
+                    })
+                    .times(1)
+                    .returning(|bundle| juice(bundle));
+            }
+
+            let client = server.client().await;
+
+            let response = client.post(format!("/api/inbox/{}/split", doc_id))
+                .header(api_key())
+                .body(json_payload!({
+                    "ranges": [
+                        { "first": 1, "last": 2 },
+                        { "first": 3, "last": 5 },
+                    ],
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get("/api/inbox")
+                .header(api_key())
+                .dispatch().await;
+
+            let list = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(list["count"]).is_equal_to(json!(2));
+            assert_that!(list["docs"].as_array().unwrap().iter().any(|doc| doc["id"] == json!(doc_id))).is_false();
+        }
+
+        #[tokio::test]
+        async fn test_merge() {
+            let mut server = Server::new().await;
+
+            let mut doc_ids = Vec::new();
+            for (label, uploaded, original, date) in &[
+                ("first", 1_000_000_000, "original.pdf", None),
+                ("second", 900_000_000, "original.png", Some(NaiveDate::from_ymd(2020, 2, 29))),
+            ] {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::other(*original)).await.unwrap()
+                    .write_all(label.as_bytes()).await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(*uploaded, 0), Utc),
+                    pages: 1,
+                    date: *date,
+                    labels: HashSet::from_iter(vec![Label::from(*label)]),
+                    properties: HashMap::from_iter(vec![
+                        (String::from("source"), label.to_string()),
+                        (label.to_string(), String::from("yes")),
+                    ]),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                doc_ids.push(*staging.create().await.unwrap().id());
+            }
+
+            server.juicer.expect_extract()
+                .withf(|bundle| {
+                    let assembly = std::fs::read(bundle.path_of(Kind::other("assemble.json"))).unwrap();
+                    let assembly = serde_json::from_slice::<serde_json::Value>(&assembly).unwrap();
+
+                    return std::fs::read(bundle.path_of(Kind::other("source-1.pdf"))).unwrap() == b"first"
+                        && std::fs::read(bundle.path_of(Kind::other("source-2.png"))).unwrap() == b"second"
+                        && assembly == json!({
+                            "interleave": false,
+                            "parts": [
+                                { "source": "source-1.pdf" },
+                                { "source": "source-2.png" },
+                            ],
+                        });
+                })
+                .times(1)
+                .return_once(|bundle| juice(bundle));
+
+            let client = server.client().await;
+
+            let response = client.post("/api/inbox/merge")
+                .header(api_key())
+                .body(json_payload!({
+                    "ids": doc_ids,
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let merged = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(merged["doc"]["metadata"]["uploaded"]).is_equal_to(json!("1998-07-09T16:00:00Z"));
+            assert_that!(merged["doc"]["metadata"]["date"]).is_equal_to(json!("2020-02-29"));
+            assert_that!(merged["doc"]["metadata"]["properties"]).is_equal_to(json!({
+                "source": "first",
+                "first": "yes",
+                "second": "yes",
+            }));
+
+            let mut labels = serde_json::from_value::<Vec<String>>(merged["doc"]["metadata"]["labels"].clone()).unwrap();
+            labels.sort();
+            assert_that!(labels).is_equal_to(vec![String::from("first"), String::from("second")]);
+
+            for doc_id in doc_ids {
+                let response = client.get(format!("/api/inbox/{}/metadata", doc_id))
+                    .header(api_key())
+                    .dispatch().await;
+
+                assert_that!(response.status()).is_equal_to(Status::NotFound);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_edit_pages() {
+            let mut server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::other("original.pdf")).await.unwrap()
+                    .write_all(b"my original document").await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"my processed document").await.unwrap();
+
+                staging.write(Kind::page_preview(3)).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                Metadata {
+                    pages: 3,
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            server.juicer.expect_extract()
+                .withf(move |bundle| {
+                    let assembly = std::fs::read(bundle.path_of(Kind::other("assemble.json"))).unwrap();
+                    let assembly = serde_json::from_slice::<serde_json::Value>(&assembly).unwrap();
+
+                    return bundle.id() == &doc_id
+                        && !bundle.path_of(Kind::other("original.pdf")).exists()
+                        && std::fs::read(bundle.path_of(Kind::other("source.pdf"))).unwrap() == b"my processed document"
+                        && assembly == json!({
+                            "interleave": false,
+                            "oriented": true,
+                            "parts": [
+                                { "source": "source.pdf", "pages": "3-3", "rotate": 90 },
+                                { "source": "source.pdf", "pages": "1-1" },
+                            ],
+                        });
+                })
+                .times(1)
+                .return_once(|bundle| {
+                    std::fs::write(bundle.path_of(Kind::other("original.pdf")), b"assembled document")?;
+                    return juice(bundle);
+                });
+
+            let client = server.client().await;
+
+            let response = client.post(format!("/api/inbox/{}/pages", doc_id))
+                .header(api_key())
+                .body(json_payload!({
+                    "pages": [
+                        { "page": 3, "rotate": 90 },
+                        { "page": 1 },
+                    ],
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get(format!("/api/inbox/{}/original.pdf", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.into_bytes().await.unwrap()).is_equal_to(b"my original document".to_vec());
+
+            let response = client.get(format!("/api/inbox/{}/edited.pdf", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.into_bytes().await.unwrap()).is_equal_to(b"assembled document".to_vec());
+
+            let response = client.get(format!("/api/inbox/{}/preview/3", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_split_invalid_range() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                Metadata {
+                    pages: 2,
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.post(format!("/api/inbox/{}/split", doc_id))
+                .header(api_key())
+                .body(json_payload!({
+                    "ranges": [
+                        { "first": 2, "last": 3 },
+                    ],
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+        }
+    }
+
+    mod archive {
+        use std::collections::HashSet;
+        use std::iter::FromIterator;
+
+        use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+        use futures::{stream, StreamExt};
+        use serde_json::json;
+        use tokio::io::AsyncWriteExt;
+
+        use crate::index::{DateRange, PropertyRange, SearchResponse};
+        use crate::meta::Metadata;
+        use crate::proto::model::{Kind, Label};
+        use crate::schema::Value;
+
+        use super::*;
+
+        #[tokio::test]
+        async fn test_get_bundle() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                staging.write(Kind::Plaintext).await.unwrap()
+                    .write_all(b"my document plaintext").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                let inboxed = staging.create().await.unwrap();
+                *inboxed.archive().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.get(format!("/api/archive/{}", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "id": doc_id.to_string(),
+                "metadata": {
+                    "uploaded": "2001-09-09T01:46:40Z",
+                    "archived": (),
+                    "title": (),
+                    "pages": 0,
+                    "date": (),
+                    "labels": [],
+                    "properties": {},
+                }
+            });
+        }
+
+        #[tokio::test]
+        async fn test_get_fragment() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                staging.write(Kind::Plaintext).await.unwrap()
+                    .write_all(b"my document plaintext").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                let inboxed = staging.create().await.unwrap();
+                *inboxed.archive().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.get(format!("/api/archive/{}/plaintext", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my document plaintext".to_vec());
+        }
+
+        #[tokio::test]
+        async fn test_get_preview() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::Document).await.unwrap()
+                    .write_all(b"").await.unwrap();
+
+                staging.write(Kind::page_preview(2)).await.unwrap()
+                    .write_all(b"my second page").await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    pages: 2,
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                let inboxed = staging.create().await.unwrap();
+                *inboxed.archive().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.get(format!("/api/archive/{}/preview/2", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.content_type()).is_equal_to(Some(ContentType::PNG));
+            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my second page".to_vec());
+
+            let response = client.get(format!("/api/archive/{}/preview/3", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_get_thumbnail() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                staging.write(Kind::thumbnail(1)).await.unwrap()
+                    .write_all(b"my first thumbnail").await.unwrap();
+
+                Metadata {
+                    pages: 1,
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                let inboxed = staging.create().await.unwrap();
+                *inboxed.archive().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let response = client.get(format!("/api/archive/{}/thumbnail/1", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_that!(response.content_type()).is_equal_to(Some(ContentType::PNG));
+            assert_that!(response.into_bytes().await).is_some().is_equal_to(b"my first thumbnail".to_vec());
+
+            let response = client.get(format!("/api/archive/{}/thumbnail/2", doc_id))
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+
+            let response = client.get("/api/archive/00000000-0000-0000-0000-000000000000/thumbnail/1")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_search() {
+            let mut server = Server::new().await;
+
+            // Create bundles in inbox (with a short delay between each to have a unique timestamp)
+            let ids = stream::iter(0..10usize).then(|_| async {
+                let bundle = server.repository.stage().await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(bundle.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                let bundle = bundle.create().await.unwrap();
+                let bundle = bundle.archive().await.unwrap();
+
+                *bundle.id()
+            }).collect::<Vec<_>>().await;
 
             server.index.expect_search()
-                .with(mockall::predicate::eq("testquery"))
+                .with(mockall::predicate::eq("testquery"),
+                      mockall::predicate::eq(DateRange::default()),
+                      mockall::predicate::eq(vec![]),
+                      mockall::predicate::eq(HashSet::new()))
                 .return_once({
                     let ids = ids.clone();
-                    move |_| Ok(SearchResponse {
+                    move |_, _, _, _| Ok(SearchResponse {
                         count: 387,
                         docs: ids,
                     })
@@ -475,26 +1526,609 @@ mod api {
 
             let client = server.client().await;
 
-            let response = client.get("/api/archive?query=testquery")
+            let response = client.get("/api/archive?query=testquery")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "count": 387,
+                "docs": ids.iter().map(|id| json!({
+                    "id": id,
+                    "metadata": {
+                        "uploaded": "2001-09-09T01:46:40Z",
+                        "archived": (),
+                        "title": (),
+                        "pages": 0,
+                        "date": (),
+                        "labels": [],
+                        "properties": {},
+                    }
+                })).collect::<Vec<_>>(),
+            });
+        }
+
+        #[tokio::test]
+        async fn test_search_date_range() {
+            let mut server = Server::new().await;
+
+            server.index.expect_search()
+                .with(mockall::predicate::eq("testquery"), mockall::predicate::eq(DateRange {
+                    from: Some(NaiveDate::from_ymd(2020, 1, 1)),
+                    to: None,
+                }), mockall::predicate::eq(vec![]), mockall::predicate::eq(HashSet::new()))
+                .return_once(|_, _, _, _| Ok(SearchResponse {
+                    count: 0,
+                    docs: vec![],
+                }));
+
+            let client = server.client().await;
+
+            let response = client.get("/api/archive?query=testquery&from=2020-01-01")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get("/api/archive?query=testquery&to=yesterday")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+        }
+
+        #[tokio::test]
+        async fn test_search_label() {
+            let mut server = Server::new().await;
+
+            server.index.expect_search()
+                .with(mockall::predicate::eq("testquery"),
+                      mockall::predicate::eq(DateRange::default()),
+                      mockall::predicate::eq(vec![]),
+                      mockall::predicate::eq(HashSet::from_iter(vec![Label::from("Finance/Bank")])))
+                .return_once(|_, _, _, _| Ok(SearchResponse {
+                    count: 0,
+                    docs: vec![],
+                }));
+
+            let client = server.client().await;
+
+            let response = client.get("/api/archive?query=testquery&label=Finance%2FBank")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+        }
+
+        #[tokio::test]
+        async fn test_search_property_range() {
+            let mut server = Server::new().await;
+
+            server.index.expect_search()
+                .with(mockall::predicate::eq("testquery"),
+                      mockall::predicate::eq(DateRange::default()),
+                      mockall::predicate::eq(vec![PropertyRange {
+                          key: String::from("amount"),
+                          min: Some(Value::Number(10.0)),
+                          max: None,
+                      }]),
+                      mockall::predicate::eq(HashSet::new()))
+                .return_once(|_, _, _, _| Ok(SearchResponse {
+                    count: 0,
+                    docs: vec![],
+                }));
+
+            let client = server.client().await;
+
+            let response = client.get("/api/archive?query=testquery&property=amount&min=10")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get("/api/archive?query=testquery&property=amount&max=much")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+
+            let response = client.get("/api/archive?query=testquery&property=unknown&min=10")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+
+            let response = client.get("/api/archive?query=testquery&min=10")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+        }
+    }
+
+    mod labels {
+        use std::collections::HashSet;
+        use std::iter::FromIterator;
+
+        use tokio::io::AsyncWriteExt;
+
+        use crate::meta::Metadata;
+        use crate::proto::model::{DocId, Kind, Label};
+
+        use super::*;
+
+        async fn archive(repository: &crate::repository::Repository, labels: &[&str]) -> DocId {
+            let staging = repository.stage().await.unwrap();
+
+            staging.write(Kind::Plaintext).await.unwrap()
+                .write_all(b"my document plaintext").await.unwrap();
+
+            Metadata {
+                labels: labels.iter().map(|&label| Label::from(label)).collect(),
+                ..Metadata::new()
+            }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+            let archived = staging.create().await.unwrap()
+                .archive().await.unwrap();
+
+            return *archived.id();
+        }
+
+        async fn labels_of(repository: &crate::repository::Repository, id: DocId) -> HashSet<Label> {
+            return repository.archive().get(id).await.unwrap()
+                .read_metadata().await.unwrap()
+                .labels;
+        }
+
+        /// Expects the suggester to be re-trained with the given labels of all archived documents.
+        fn expect_retrain(server: &mut Server, labels: Vec<HashSet<Label>>) {
+            server.suggester.expect_rebuild()
+                .withf(move |documents| {
+                    // The documents are listed in arbitrary order
+                    let mut expected = labels.clone();
+                    for (text, labels) in documents {
+                        match expected.iter().position(|other| other == labels) {
+                            Some(index) if text == "my document plaintext" => { expected.remove(index); }
+                            _ => return false,
+                        }
+                    }
+
+                    return expected.is_empty();
+                })
+                .times(1)
+                .returning(|_| Ok(()));
+
+            server.suggester.expect_flush()
+                .times(1)
+                .returning(|| Ok(()));
+        }
+
+        #[tokio::test]
+        async fn test_describe() {
+            let server = Server::new().await;
+
+            archive(&server.repository, &["invoice"]).await;
+            archive(&server.repository, &["invoice", "energy"]).await;
+
+            let client = server.client().await;
+
+            let response = client.put("/api/labels?label=insurance")
+                .header(api_key())
+                .body(json_payload!({
+                    "description": "Policies and claims",
+                    "color": "#1e90ff",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get("/api/labels")
                 .header(api_key())
                 .dispatch().await;
 
             assert_that!(response.status()).is_equal_to(Status::Ok);
 
             assert_json_eq!(response.into_bytes().await.unwrap(), {
-                "count": 387,
-                "docs": ids.iter().map(|id| json!({
-                    "id": id,
-                    "metadata": {
-                        "uploaded": "2001-09-09T01:46:40Z",
-                        "archived": (),
-                        "title": (),
-                        "pages": 0,
-                        "labels": [],
-                        "properties": {},
-                    }
-                })).collect::<Vec<_>>(),
+                "labels": [
+                    { "label": "energy", "count": 1, "total": 1 },
+                    { "label": "insurance", "count": 0, "total": 0, "description": "Policies and claims", "color": "#1e90ff" },
+                    { "label": "invoice", "count": 2, "total": 2 },
+                ]
+            });
+        }
+
+        #[tokio::test]
+        async fn test_list_tree() {
+            let server = Server::new().await;
+
+            archive(&server.repository, &["Finance/Bank/Statements", "Home"]).await;
+            archive(&server.repository, &["Finance/Bank/Statements", "Finance/Tax"]).await;
+            archive(&server.repository, &["Finance"]).await;
+
+            let client = server.client().await;
+
+            let response = client.get("/api/labels")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "labels": [
+                    { "label": "Finance", "count": 1, "total": 3, "children": [
+                        { "label": "Finance/Bank", "count": 0, "total": 2, "children": [
+                            { "label": "Finance/Bank/Statements", "count": 2, "total": 2 },
+                        ] },
+                        { "label": "Finance/Tax", "count": 1, "total": 1 },
+                    ] },
+                    { "label": "Home", "count": 1, "total": 1 },
+                ]
+            });
+        }
+
+        #[tokio::test]
+        async fn test_describe_invalid_label() {
+            let server = Server::new().await;
+            let client = server.client().await;
+
+            for label in &["Finance%2F%2FBank", "Finance%2F", "%2FFinance", "Finance%2F%20Bank"] {
+                let response = client.put(format!("/api/labels?label={}", label))
+                    .header(api_key())
+                    .body(json_payload!({}))
+                    .dispatch().await;
+
+                assert_that!(response.status()).is_equal_to(Status::BadRequest);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_describe_invalid_color() {
+            let server = Server::new().await;
+            let client = server.client().await;
+
+            let response = client.put("/api/labels?label=insurance")
+                .header(api_key())
+                .body(json_payload!({
+                    "color": "blue",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+        }
+
+        #[tokio::test]
+        async fn test_rename() {
+            let mut server = Server::new().await;
+
+            let first = archive(&server.repository, &["invoice", "energy"]).await;
+            let second = archive(&server.repository, &["energy"]).await;
+
+            server.index.expect_index()
+                .withf(move |bundle| bundle.id() == &first)
+                .times(1)
+                .returning(|_| Ok(()));
+
+            expect_retrain(&mut server, vec![
+                HashSet::from_iter(vec![Label::from("bill"), Label::from("energy")]),
+                HashSet::from_iter(vec![Label::from("energy")]),
+            ]);
+
+            let repository = server.repository.path().to_path_buf();
+            let client = server.client().await;
+
+            let response = client.post("/api/labels/rename?label=invoice")
+                .header(api_key())
+                .body(json_payload!({
+                    "to": "bill",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "documents": 1,
+            });
+
+            let repository = crate::repository::Repository::with_path(repository).await.unwrap();
+            assert_that!(labels_of(&repository, first).await).is_equal_to(HashSet::from_iter(vec![Label::from("bill"), Label::from("energy")]));
+            assert_that!(labels_of(&repository, second).await).is_equal_to(HashSet::from_iter(vec![Label::from("energy")]));
+        }
+
+        #[tokio::test]
+        async fn test_rename_existing() {
+            let server = Server::new().await;
+
+            archive(&server.repository, &["invoice"]).await;
+            archive(&server.repository, &["bill"]).await;
+
+            let client = server.client().await;
+
+            let response = client.post("/api/labels/rename?label=invoice")
+                .header(api_key())
+                .body(json_payload!({
+                    "to": "bill",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::BadRequest);
+
+            let response = client.post("/api/labels/rename?label=unknown")
+                .header(api_key())
+                .body(json_payload!({
+                    "to": "other",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_merge() {
+            let mut server = Server::new().await;
+
+            let first = archive(&server.repository, &["invoice"]).await;
+            let second = archive(&server.repository, &["bill", "energy"]).await;
+
+            server.index.expect_index()
+                .withf(move |bundle| bundle.id() == &first)
+                .times(1)
+                .returning(|_| Ok(()));
+
+            expect_retrain(&mut server, vec![
+                HashSet::from_iter(vec![Label::from("bill")]),
+                HashSet::from_iter(vec![Label::from("bill"), Label::from("energy")]),
+            ]);
+
+            let repository = server.repository.path().to_path_buf();
+            let client = server.client().await;
+
+            let response = client.post("/api/labels/merge?label=invoice")
+                .header(api_key())
+                .body(json_payload!({
+                    "into": "bill",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+            assert_json_eq!(response.into_bytes().await.unwrap(), {
+                "documents": 1,
             });
+
+            let repository = crate::repository::Repository::with_path(repository).await.unwrap();
+            assert_that!(labels_of(&repository, first).await).is_equal_to(HashSet::from_iter(vec![Label::from("bill")]));
+            assert_that!(labels_of(&repository, second).await).is_equal_to(HashSet::from_iter(vec![Label::from("bill"), Label::from("energy")]));
+        }
+
+        #[tokio::test]
+        async fn test_delete() {
+            let mut server = Server::new().await;
+
+            let doc_id = archive(&server.repository, &["invoice", "energy"]).await;
+
+            server.index.expect_index()
+                .withf(move |bundle| bundle.id() == &doc_id)
+                .times(1)
+                .returning(|_| Ok(()));
+
+            expect_retrain(&mut server, vec![
+                HashSet::from_iter(vec![Label::from("energy")]),
+            ]);
+
+            let repository = server.repository.path().to_path_buf();
+            let client = server.client().await;
+
+            let response = client.delete("/api/labels?label=invoice")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.delete("/api/labels?label=invoice")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+
+            let repository = crate::repository::Repository::with_path(repository).await.unwrap();
+            assert_that!(labels_of(&repository, doc_id).await).is_equal_to(HashSet::from_iter(vec![Label::from("energy")]));
+        }
+
+        #[tokio::test]
+        async fn test_stats_invalid_folds() {
+            let server = Server::new().await;
+            let client = server.client().await;
+
+            for folds in &[0, 1, 100] {
+                let response = client.get(format!("/api/labels/stats?folds={}", folds))
+                    .header(api_key())
+                    .dispatch().await;
+
+                assert_that!(response.status()).is_equal_to(Status::BadRequest);
+            }
+        }
+    }
+
+    mod keys {
+        use chrono::{DateTime, NaiveDateTime, Utc};
+        use serde_json::json;
+
+        use crate::meta::Metadata;
+        use crate::proto::model::Kind;
+
+        use super::*;
+
+        /// Creates a key restricted to the given scopes and returns its authorization header.
+        async fn restricted(client: &rocket::local::asynchronous::Client, name: &str, scopes: serde_json::Value) -> Header<'static> {
+            let response = client.post("/api/keys")
+                .header(api_key())
+                .body(json_payload!({
+                    "name": name,
+                    "scopes": scopes,
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let created = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let basic = base64::encode(format!("{}:{}", name, created["secret"].as_str().unwrap()));
+
+            return Header::new("Authorization", format!("Basic {}", basic));
+        }
+
+        #[tokio::test]
+        async fn test_create_and_revoke() {
+            let server = Server::new().await;
+            let client = server.client().await;
+
+            let response = client.post("/api/keys")
+                .header(api_key())
+                .body(json_payload!({
+                    "name": "scanner",
+                    "scopes": [ "upload" ],
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let created = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let secret = created["secret"].as_str().unwrap().to_string();
+
+            let scanner = || {
+                let basic = base64::encode(format!("{}:{}", "scanner", secret));
+                return Header::new("Authorization", format!("Basic {}", basic));
+            };
+
+            // The key is restricted to uploads
+            let response = client.get("/api/keys")
+                .header(scanner())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Forbidden);
+
+            let response = client.get("/api/keys")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let list = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(list["keys"][0]["name"]).is_equal_to(json!("scanner"));
+            assert_that!(list["keys"][0]["scopes"]).is_equal_to(json!(["upload"]));
+            assert_that!(list["keys"][0]["last_used"].is_string()).is_true();
+            assert_that!(list["keys"][0].get("secret")).is_none();
+
+            let response = client.delete("/api/keys/scanner")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            let response = client.get("/api/keys")
+                .header(scanner())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Unauthorized);
+        }
+
+        #[tokio::test]
+        async fn test_create_invalid() {
+            let server = Server::new().await;
+            let client = server.client().await;
+
+            for name in &["test", "invalid:name", ""] {
+                let response = client.post("/api/keys")
+                    .header(api_key())
+                    .body(json_payload!({
+                        "name": name,
+                    }))
+                    .dispatch().await;
+
+                assert_that!(response.status()).is_equal_to(Status::BadRequest);
+            }
+
+            // A key can not grant more than its own scopes
+            let response = client.post("/api/keys")
+                .header(api_key())
+                .body(json_payload!({
+                    "name": "admin",
+                    "scopes": [ "admin" ],
+                }))
+                .dispatch().await;
+
+            let created = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            let basic = base64::encode(format!("{}:{}", "admin", created["secret"].as_str().unwrap()));
+
+            let response = client.post("/api/keys")
+                .header(Header::new("Authorization", format!("Basic {}", basic)))
+                .body(json_payload!({
+                    "name": "unrestricted",
+                }))
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Forbidden);
+
+            let response = client.delete("/api/keys/unknown")
+                .header(api_key())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::NotFound);
+        }
+
+        #[tokio::test]
+        async fn test_scopes() {
+            let server = Server::new().await;
+
+            let doc_id = {
+                let staging = server.repository.stage().await.unwrap();
+
+                Metadata {
+                    uploaded: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(1_000_000_000, 0), Utc),
+                    ..Metadata::new()
+                }.save(staging.write(Kind::Metadata).await.unwrap()).await.unwrap();
+
+                *staging.create().await.unwrap().id()
+            };
+
+            let client = server.client().await;
+
+            let scanner = restricted(&client, "scanner", json!(["upload"])).await;
+            let viewer = restricted(&client, "viewer", json!(["read"])).await;
+
+            // Each route group requires its own scope
+            let forbidden = vec![
+                (&scanner, client.get("/api/archive?query=anything")),
+                (&scanner, client.get("/api/labels")),
+                (&scanner, client.get("/api/inbox")),
+                (&scanner, client.delete(format!("/api/inbox/{}", doc_id))),
+                (&scanner, client.post("/api/labels/rename?label=Finance").body(json_payload!({ "to": "Money" }))),
+                (&scanner, client.delete("/api/labels?label=Finance")),
+                (&scanner, client.get("/api/labels/stats")),
+                (&scanner, client.get("/api/audit")),
+                (&scanner, client.get("/api/keys")),
+                (&viewer, client.post("/api/upload").header(ContentType::PDF).body(b"%PDF")),
+                (&viewer, client.delete(format!("/api/inbox/{}", doc_id))),
+                (&viewer, client.post("/api/labels/rename?label=Finance").body(json_payload!({ "to": "Money" }))),
+                (&viewer, client.get("/api/audit")),
+            ];
+
+            for (key, request) in forbidden {
+                let response = request.header(key.clone()).dispatch().await;
+                assert_that!(response.status()).is_equal_to(Status::Forbidden);
+            }
+
+            let response = client.get("/api/labels")
+                .header(viewer.clone())
+                .dispatch().await;
+
+            assert_that!(response.status()).is_equal_to(Status::Ok);
+
+            // The document has not been deleted
+            let response = client.get("/api/inbox")
+                .header(api_key())
+                .dispatch().await;
+
+            let list = serde_json::from_slice::<serde_json::Value>(&response.into_bytes().await.unwrap()).unwrap();
+            assert_that!(list["count"]).is_equal_to(json!(1));
         }
     }
 }
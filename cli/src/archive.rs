@@ -50,8 +50,13 @@ pub async fn get(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<
 
 pub async fn search(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
     let query = matches.value_of("query").expect("Required query missing");
+    let from = matches.value_of("from");
+    let to = matches.value_of("to");
+    let label = matches.value_of("label");
+    let property = matches.value_of("property")
+        .map(|property| (property, matches.value_of("min"), matches.value_of("max")));
 
-    let response = client.archive_search(query).await?;
+    let response = client.archive_search(query, from, to, label, property).await?;
     return Ok(Box::new(response));
 }
 
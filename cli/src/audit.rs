@@ -0,0 +1,34 @@
+use std::io::Write;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::client::Client;
+use crate::output::{Output, SimpleOutput};
+use crate::proto::api::audit::{Action, ListResponse};
+
+pub async fn exec(_: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let response = client.audit_list().await?;
+
+    return Ok(Box::new(response));
+}
+
+impl SimpleOutput for ListResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        if self.entries.is_empty() {
+            writeln!(w, "{} {}", "❌".bright_red(), "No actions recorded".red())?;
+        }
+
+        for entry in &self.entries {
+            let (action, doc) = match &entry.action {
+                Action::Upload { doc } => ("uploaded", doc),
+                Action::Archive { doc } => ("archived", doc),
+                Action::Delete { doc } => ("deleted", doc),
+            };
+
+            writeln!(w, "{} {} {} {}", entry.time.to_string().dimmed(), entry.user.bold(), action, doc.to_string().cyan())?;
+        }
+
+        return Ok(());
+    }
+}
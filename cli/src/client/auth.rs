@@ -1,13 +1,17 @@
+use std::collections::HashSet;
+
 use anyhow::Result;
-use reqwest::header::HeaderValue;
 use reqwest::Url;
 
-use crate::proto::api::auth::AuthRequest;
+use crate::proto::api::auth::{AuthRequest, AuthResponse};
+use crate::proto::model::Scope;
 
 #[derive(Debug)]
 pub enum Auth {
     Login {
+        username: Option<String>,
         password: String,
+        scopes: Option<HashSet<Scope>>,
     },
 
     ApiKey {
@@ -17,8 +21,8 @@ pub enum Auth {
 }
 
 impl Auth {
-    pub fn login(password: String) -> Self {
-        return Self::Login { password };
+    pub fn login(username: Option<String>, password: String, scopes: Option<HashSet<Scope>>) -> Self {
+        return Self::Login { username, password, scopes };
     }
 
     pub fn api_key(username: String, password: String) -> Self {
@@ -39,17 +43,14 @@ pub(super) enum Session {
 impl Session {
     pub(super) async fn authenticate(auth: Auth, base_url: &Url, client: &reqwest::Client) -> Result<Self> {
         match auth {
-            Auth::Login { password } => {
+            Auth::Login { username, password, scopes } => {
                 let response = client.post(&format!("{}/auth/login", base_url))
-                    .json(&AuthRequest { password })
+                    .json(&AuthRequest { username, password, scopes })
                     .send().await?
-                    .error_for_status()?;
-
-                let token = response.headers().get(reqwest::header::AUTHORIZATION)
-                    .expect("Token missing in response")
-                    .to_str().expect("Token invalid");
+                    .error_for_status()?
+                    .json::<AuthResponse>().await?;
 
-                return Ok(Self::Token { token: token.to_string() });
+                return Ok(Self::Token { token: response.access });
             }
 
             Auth::ApiKey { username, password } => {
@@ -66,14 +67,6 @@ impl Session {
 
         let response = request.send().await?;
 
-        let token = response.headers().get(reqwest::header::AUTHORIZATION)
-            .map(HeaderValue::to_str)
-            .transpose()?;
-
-        if let (Self::Token { token }, Some(update)) = (self, token) {
-            *token = update.to_string();
-        }
-
         return Ok(response);
     }
 }
@@ -1,13 +1,15 @@
 use anyhow::Result;
 use futures::{SinkExt, StreamExt, TryStreamExt};
 use reqwest::{Body, Method, RequestBuilder, Url};
+use reqwest::multipart::{Form, Part};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite};
 
 pub use auth::Auth;
 use auth::Session;
 
-use crate::proto::api::{archive, inbox, upload};
+use crate::proto::api::{archive, audit, inbox, keys, labels, upload};
+use crate::proto::model::LabelInfo;
 
 pub mod auth;
 
@@ -47,13 +49,13 @@ impl Client {
         return Ok(request);
     }
 
-    pub async fn upload(&mut self, r: impl AsyncRead + Send + Sync + 'static) -> Result<upload::UploadResponse> {
+    pub async fn upload(&mut self, r: impl AsyncRead + Send + Sync + 'static, content_type: &str) -> Result<upload::UploadResponse> {
         let request = self.request(Method::POST, "/upload")?;
 
         let r = FramedRead::new(r, BytesCodec::new());
         let r = Body::wrap_stream(r);
         let request = request.body(r)
-            .header(reqwest::header::CONTENT_TYPE, "application/pdf");
+            .header(reqwest::header::CONTENT_TYPE, content_type);
 
 
         let response = self.session.send(request).await?
@@ -62,6 +64,28 @@ impl Client {
         return Ok(response.json().await?);
     }
 
+    pub async fn upload_merged(&mut self,
+                               documents: Vec<(impl AsyncRead + Send + Sync + 'static, &str)>,
+                               interleave: bool,
+                               reverse: bool) -> Result<upload::UploadResponse> {
+        let request = self.request(Method::POST, "/upload")?;
+
+        let mut form = Form::new();
+        for (r, content_type) in documents {
+            let r = FramedRead::new(r, BytesCodec::new());
+            let r = Body::wrap_stream(r);
+            form = form.part("document", Part::stream(r).mime_str(content_type)?);
+        }
+
+        let request = request.multipart(form)
+            .query(&[("interleave", interleave), ("reverse", reverse)]);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
     pub async fn inbox_list(&mut self) -> Result<inbox::ListResponse> {
         let request = self.request(Method::GET, "/inbox")?;
 
@@ -71,8 +95,12 @@ impl Client {
         return Ok(response.json().await?);
     }
 
-    pub async fn inbox_bundle(&mut self, id: &str) -> Result<inbox::GetResponse> {
+    pub async fn inbox_bundle(&mut self, id: &str, certainty: Option<f64>) -> Result<inbox::GetResponse> {
         let request = self.request(Method::GET, &format!("/inbox/{}", id))?;
+        let request = match certainty {
+            Some(certainty) => request.query(&[("certainty", certainty)]),
+            None => request,
+        };
 
         let response = self.session.send(request).await?
             .error_for_status()?;
@@ -112,6 +140,36 @@ impl Client {
         return Ok(());
     }
 
+    pub async fn inbox_split(&mut self, id: &str, data: &inbox::SplitRequest) -> Result<inbox::SplitResponse> {
+        let request = self.request(Method::POST, &format!("/inbox/{}/split", id))?;
+        let request = request.json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn inbox_merge(&mut self, data: &inbox::MergeRequest) -> Result<inbox::MergeResponse> {
+        let request = self.request(Method::POST, "/inbox/merge")?;
+        let request = request.json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn inbox_edit(&mut self, id: &str, data: &inbox::EditRequest) -> Result<inbox::EditResponse> {
+        let request = self.request(Method::POST, &format!("/inbox/{}/pages", id))?;
+        let request = request.json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
     pub async fn archive_bundle(&mut self, id: &str) -> Result<archive::BundleResponse> {
         let request = self.request(Method::GET, &format!("/archive/{}", id))?;
 
@@ -134,9 +192,124 @@ impl Client {
             .forward(w.sink_err_into()).await;
     }
 
-    pub async fn archive_search(&mut self, query: &str) -> Result<archive::SearchResponse> {
+    pub async fn archive_search(&mut self,
+                                query: &str,
+                                from: Option<&str>,
+                                to: Option<&str>,
+                                label: Option<&str>,
+                                property: Option<(&str, Option<&str>, Option<&str>)>) -> Result<archive::SearchResponse> {
         let request = self.request(Method::GET, "/archive")?;
-        let request = request.query(&[("query", query)]);
+
+        let mut params = vec![("query", query)];
+        if let Some(from) = from {
+            params.push(("from", from));
+        }
+        if let Some(to) = to {
+            params.push(("to", to));
+        }
+        if let Some(label) = label {
+            params.push(("label", label));
+        }
+        if let Some((property, min, max)) = property {
+            params.push(("property", property));
+            if let Some(min) = min {
+                params.push(("min", min));
+            }
+            if let Some(max) = max {
+                params.push(("max", max));
+            }
+        }
+
+        let request = request.query(&params);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn labels_list(&mut self) -> Result<labels::ListResponse> {
+        let request = self.request(Method::GET, "/labels")?;
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn labels_describe(&mut self, label: &str, data: &LabelInfo) -> Result<labels::LabelResponse> {
+        let request = self.request(Method::PUT, "/labels")?;
+        let request = request.query(&[("label", label)])
+            .json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn labels_rename(&mut self, label: &str, data: &labels::RenameRequest) -> Result<labels::ChangeResponse> {
+        let request = self.request(Method::POST, "/labels/rename")?;
+        let request = request.query(&[("label", label)])
+            .json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn labels_merge(&mut self, label: &str, data: &labels::MergeRequest) -> Result<labels::ChangeResponse> {
+        let request = self.request(Method::POST, "/labels/merge")?;
+        let request = request.query(&[("label", label)])
+            .json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn labels_delete(&mut self, label: &str) -> Result<labels::ChangeResponse> {
+        let request = self.request(Method::DELETE, "/labels")?;
+        let request = request.query(&[("label", label)]);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn keys_list(&mut self) -> Result<keys::ListResponse> {
+        let request = self.request(Method::GET, "/keys")?;
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn keys_create(&mut self, data: &keys::CreateRequest) -> Result<keys::CreateResponse> {
+        let request = self.request(Method::POST, "/keys")?;
+        let request = request.json(data);
+
+        let response = self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(response.json().await?);
+    }
+
+    pub async fn keys_revoke(&mut self, name: &str) -> Result<()> {
+        let request = self.request(Method::DELETE, &format!("/keys/{}", name))?;
+
+        self.session.send(request).await?
+            .error_for_status()?;
+
+        return Ok(());
+    }
+
+    pub async fn audit_list(&mut self) -> Result<audit::ListResponse> {
+        let request = self.request(Method::GET, "/audit")?;
 
         let response = self.session.send(request).await?
             .error_for_status()?;
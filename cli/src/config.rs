@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::path::Path;
 
@@ -5,13 +6,20 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use crate::client;
 use crate::output::Output;
+use crate::proto::model::Scope;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Auth {
     #[serde(rename = "login")]
     Login {
+        #[serde(rename = "username", default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+
         #[serde(rename = "password")]
         password: String,
+
+        #[serde(rename = "scopes", default, skip_serializing_if = "Option::is_none")]
+        scopes: Option<HashSet<Scope>>,
     },
 
     #[serde(rename = "apiKey")]
@@ -25,8 +33,8 @@ pub enum Auth {
 }
 
 impl Auth {
-    pub fn login(password: String) -> Self {
-        return Self::Login { password };
+    pub fn login(username: Option<String>, password: String, scopes: Option<HashSet<Scope>>) -> Self {
+        return Self::Login { username, password, scopes };
     }
 
     pub fn api_key(username: String, password: String) -> Self {
@@ -37,7 +45,7 @@ impl Auth {
 impl Into<client::Auth> for Auth {
     fn into(self) -> client::Auth {
         return match self {
-            Self::Login { password } => client::Auth::login(password),
+            Self::Login { username, password, scopes } => client::Auth::login(username, password, scopes),
             Self::ApiKey { username, password } => client::Auth::api_key(username, password),
         };
     }
@@ -91,10 +99,13 @@ pub fn exec(matches: &clap::ArgMatches<'_>) -> Result<Box<dyn Output>> {
     let username = matches.value_of("username");
     let password = matches.value_of("password").expect("Password required");
 
-    let auth = if let Some(username) = username {
-        Auth::api_key(username.to_string(), password.to_string())
-    } else {
-        Auth::login(password.to_string())
+    let scopes = matches.values_of("scopes")
+        .map(|scopes| scopes.map(str::parse::<Scope>).collect::<Result<_>>())
+        .transpose()?;
+
+    let auth = match username {
+        Some(username) if !matches.is_present("login") => Auth::api_key(username.to_string(), password.to_string()),
+        username => Auth::login(username.map(str::to_string), password.to_string(), scopes),
     };
 
     let config = Config::with(target.to_owned(), auth);
@@ -1,12 +1,15 @@
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
 use colored::Colorize;
 
 use crate::client::Client;
 use crate::output::{Output, SimpleOutput};
-use crate::proto::api::inbox::{ArchiveRequest, GetResponse, ListResponse};
+use crate::proto::api::inbox::{ArchiveRequest, EditRequest, EditResponse, GetResponse, ListResponse, MergeRequest, MergeResponse, SplitRequest, SplitResponse};
+use crate::proto::model::{DocId, PageEdit, PageRange};
 
 pub async fn exec(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
     return match matches.subcommand() {
@@ -15,6 +18,9 @@ pub async fn exec(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result
         ("get", Some(matches)) => get(matches, client).await,
         ("delete", Some(matches)) => delete(matches, client).await,
         ("archive", Some(matches)) => archive(matches, client).await,
+        ("split", Some(matches)) => split(matches, client).await,
+        ("merge", Some(matches)) => merge(matches, client).await,
+        ("edit", Some(matches)) => edit(matches, client).await,
 
         _ => unreachable!()
     };
@@ -30,7 +36,12 @@ pub async fn list(_: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<d
 pub async fn show(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
     let id = matches.value_of("id").expect("Required ID missing");
 
-    let response = client.inbox_bundle(id).await?;
+    let certainty = matches.value_of("certainty")
+        .map(|certainty| certainty.parse::<f64>()
+            .map_err(|err| anyhow!("Invalid certainty {}: {}", certainty, err)))
+        .transpose()?;
+
+    let response = client.inbox_bundle(id, certainty).await?;
 
     return Ok(Box::new(response));
 }
@@ -75,11 +86,20 @@ pub async fn archive(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Res
         .unwrap_or_else(HashSet::default);
 
     // TODO: Custom typed parsers in clap?
-    let properties = matches.values_of("labels")
+    let properties = matches.values_of("properties")
         .map(|properties| properties.map(|property| property.split_once('=').unwrap_or((property, ""))).map(|(k, v)| (k.to_string(), v.to_string())).collect())
         .unwrap_or_else(HashMap::default);
 
+    let title = matches.value_of("title").map(str::to_string);
+
+    let date = matches.value_of("date")
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| anyhow!("Invalid date {}: {}", date, err)))
+        .transpose()?;
+
     let data = ArchiveRequest {
+        title,
+        date,
         labels,
         properties,
     };
@@ -89,6 +109,59 @@ pub async fn archive(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Res
     return Ok(Box::new(()));
 }
 
+pub async fn split(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let id = matches.value_of("id").expect("Required ID missing");
+
+    let ranges = matches.values_of("ranges").expect("Required ranges missing")
+        .map(PageRange::from_str)
+        .collect::<Result<_>>()?;
+
+    let data = SplitRequest {
+        ranges,
+    };
+
+    let response = client.inbox_split(id, &data).await?;
+
+    return Ok(Box::new(response));
+}
+
+pub async fn merge(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let ids = matches.values_of("ids").expect("Required IDs missing")
+        .map(DocId::from_str)
+        .collect::<Result<_>>()?;
+
+    let data = MergeRequest {
+        ids,
+    };
+
+    let response = client.inbox_merge(&data).await?;
+
+    return Ok(Box::new(response));
+}
+
+pub async fn edit(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let id = matches.value_of("id").expect("Required ID missing");
+
+    let mut pages = Vec::new();
+    for value in matches.values_of("pages").expect("Required pages missing") {
+        let (range, rotate) = value.split_once(':').unwrap_or((value, "0"));
+
+        let range = PageRange::from_str(range)?;
+        let rotate = rotate.parse()
+            .map_err(|_| anyhow!("Invalid rotation: {}", rotate))?;
+
+        pages.extend((range.first..=range.last).map(|page| PageEdit { page, rotate }));
+    }
+
+    let data = EditRequest {
+        pages,
+    };
+
+    let response = client.inbox_edit(id, &data).await?;
+
+    return Ok(Box::new(response));
+}
+
 impl SimpleOutput for ListResponse {
     fn to_text(&self, w: &mut dyn Write) -> Result<()> {
         if self.count == 0 {
@@ -109,11 +182,47 @@ impl SimpleOutput for GetResponse {
     fn to_text(&self, w: &mut dyn Write) -> Result<()> {
         SimpleOutput::to_text(&self.doc, w)?;
 
+        writeln!(w, "    {}: {}", "Suggested Title".bold(), self.title_suggestion.as_deref().unwrap_or(""))?;
+
         writeln!(w, "    {}:", "Suggested Labels".bold())?;
         for suggestion in self.suggestions.iter() {
-            writeln!(w, "        {} {}", "-".white(), suggestion)?;
+            writeln!(w, "        {} {} {}", "-".white(), suggestion.label, format!("({:.0}%)", suggestion.score * 100.0).dimmed())?;
+        }
+
+        writeln!(w, "    {}:", "Suggested Dates".bold())?;
+        for date in self.date_suggestions.iter() {
+            writeln!(w, "        {} {}", "-".white(), date)?;
+        }
+
+        writeln!(w, "    {}:", "Suggested Properties".bold())?;
+        for (key, values) in self.property_suggestions.iter() {
+            for value in values {
+                writeln!(w, "        {} {} {} {}", "-".white(), key.bold(), "🢒".white(), value)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl SimpleOutput for SplitResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        for doc in &self.docs {
+            SimpleOutput::to_text(doc, w)?;
         }
 
         return Ok(());
     }
 }
+
+impl SimpleOutput for MergeResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        return SimpleOutput::to_text(&self.doc, w);
+    }
+}
+
+impl SimpleOutput for EditResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        return SimpleOutput::to_text(&self.doc, w);
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::client::Client;
+use crate::output::{Output, SimpleOutput};
+use crate::proto::api::keys::{CreateRequest, CreateResponse, ListResponse};
+use crate::proto::model::Scope;
+
+pub async fn exec(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    return match matches.subcommand() {
+        ("list", Some(matches)) => list(matches, client).await,
+        ("create", Some(matches)) => create(matches, client).await,
+        ("revoke", Some(matches)) => revoke(matches, client).await,
+
+        _ => unreachable!()
+    };
+}
+
+pub async fn list(_: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let response = client.keys_list().await?;
+
+    return Ok(Box::new(response));
+}
+
+pub async fn create(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let name = matches.value_of("name").expect("Required name missing");
+
+    let scopes = matches.values_of("scopes")
+        .map(|scopes| scopes.map(str::parse::<Scope>).collect::<Result<_>>())
+        .transpose()?;
+
+    let response = client.keys_create(&CreateRequest {
+        name: name.to_string(),
+        scopes,
+    }).await?;
+    return Ok(Box::new(response));
+}
+
+pub async fn revoke(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let name = matches.value_of("name").expect("Required name missing");
+
+    client.keys_revoke(name).await?;
+
+    return Ok(Box::new(()));
+}
+
+impl SimpleOutput for CreateResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        writeln!(w, "{} {}", "✓".bright_green(), format!("Created API key {}", self.name).green())?;
+        writeln!(w, "    {}: {}", "Secret".bold(), self.secret)?;
+        writeln!(w, "{}", "The secret is shown only once - store it now".yellow())?;
+
+        return Ok(());
+    }
+}
+
+impl SimpleOutput for ListResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        if self.keys.is_empty() {
+            writeln!(w, "{} {}", "❌".bright_red(), "No API keys found".red())?;
+        }
+
+        for key in &self.keys {
+            writeln!(w, "{} {}", "🔑".bright_cyan(), key.name.cyan())?;
+            writeln!(w, "    {}: {}", "Created".bold(), key.created.to_string())?;
+            writeln!(w, "    {}: {}", "Last used".bold(), key.last_used.map(|time| time.to_string()).unwrap_or_else(|| String::from("never")))?;
+
+            if let Some(scopes) = &key.scopes {
+                let mut scopes = scopes.iter().map(Scope::to_string).collect::<Vec<_>>();
+                scopes.sort();
+                writeln!(w, "    {}: {}", "Scopes".bold(), scopes.join(", "))?;
+            }
+        }
+
+        return Ok(());
+    }
+}
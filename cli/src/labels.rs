@@ -0,0 +1,116 @@
+use std::io::Write;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::client::Client;
+use crate::output::{Output, SimpleOutput};
+use crate::proto::api::labels::{ChangeResponse, LabelResponse, ListResponse, MergeRequest, RenameRequest};
+use crate::proto::model::{Label, LabelInfo};
+
+pub async fn exec(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    return match matches.subcommand() {
+        ("list", Some(matches)) => list(matches, client).await,
+        ("describe", Some(matches)) => describe(matches, client).await,
+        ("rename", Some(matches)) => rename(matches, client).await,
+        ("merge", Some(matches)) => merge(matches, client).await,
+        ("delete", Some(matches)) => delete(matches, client).await,
+
+        _ => unreachable!()
+    };
+}
+
+pub async fn list(_: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let response = client.labels_list().await?;
+
+    return Ok(Box::new(response));
+}
+
+pub async fn describe(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let label = matches.value_of("label").expect("Required label missing");
+
+    let info = LabelInfo {
+        description: matches.value_of("description").map(str::to_string),
+        color: matches.value_of("color").map(str::to_string),
+    };
+
+    let response = client.labels_describe(label, &info).await?;
+    return Ok(Box::new(response));
+}
+
+pub async fn rename(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let label = matches.value_of("label").expect("Required label missing");
+    let to = matches.value_of("to").expect("Required label missing");
+
+    let response = client.labels_rename(label, &RenameRequest {
+        to: Label::from(to),
+    }).await?;
+    return Ok(Box::new(response));
+}
+
+pub async fn merge(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let label = matches.value_of("label").expect("Required label missing");
+    let into = matches.value_of("into").expect("Required label missing");
+
+    let response = client.labels_merge(label, &MergeRequest {
+        into: Label::from(into),
+    }).await?;
+    return Ok(Box::new(response));
+}
+
+pub async fn delete(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
+    let label = matches.value_of("label").expect("Required label missing");
+
+    let response = client.labels_delete(label).await?;
+    return Ok(Box::new(response));
+}
+
+impl SimpleOutput for LabelResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        return write_label(self, 0, w);
+    }
+}
+
+fn write_label(label: &LabelResponse, depth: usize, w: &mut dyn Write) -> Result<()> {
+    let name = if depth == 0 { label.label.as_str() } else { label.label.name() };
+
+    write!(w, "{}{} {} {}", "    ".repeat(depth), "🏷".bright_cyan(), name.cyan(), format!("({} documents)", label.total).dimmed())?;
+
+    if let Some(color) = &label.info.color {
+        write!(w, " {}", color.dimmed())?;
+    }
+
+    if let Some(description) = &label.info.description {
+        write!(w, " {} {}", "🢒".white(), description)?;
+    }
+
+    writeln!(w)?;
+
+    for child in &label.children {
+        write_label(child, depth + 1, w)?;
+    }
+
+    return Ok(());
+}
+
+impl SimpleOutput for ListResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        if self.labels.is_empty() {
+            writeln!(w, "{} {}", "❌".bright_red(), "No labels found".red())?;
+        }
+
+        for label in &self.labels {
+            write_label(label, 0, w)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl SimpleOutput for ChangeResponse {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        writeln!(w, "{} {}", "✓".bright_green(), format!("{} documents changed", self.documents).green())?;
+
+        return Ok(());
+    }
+}
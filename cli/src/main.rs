@@ -16,6 +16,9 @@ mod client;
 mod upload;
 mod inbox;
 mod archive;
+mod labels;
+mod audit;
+mod keys;
 
 #[tokio::main]
 async fn main() {
@@ -54,22 +57,49 @@ async fn main() {
                 .takes_value(true)
                 .required(true))
             .arg(Arg::with_name("username")
-                .help("The application key username")
+                .help("The application key or login username")
                 .long("username")
                 .short("u")
                 .takes_value(true))
+            .arg(Arg::with_name("login")
+                .help("Log in to a user account instead of using an application key")
+                .long("login")
+                .short("l")
+                .requires("username"))
             .arg(Arg::with_name("password")
                 .long("password")
                 .short("p")
                 .help("The login or application key password")
                 .takes_value(true)
-                .required(true)))
+                .required(true))
+            .arg(Arg::with_name("scopes")
+                .long("scope")
+                .short("s")
+                .help("Restrict the login session to a scope (upload, read, review or admin)")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)))
         .subcommand(SubCommand::with_name("upload")
             .about("Uploads a document")
-            .arg(Arg::with_name("pdf")
-                .help("The PDF document to upload")
+            .arg(Arg::with_name("documents")
+                .help("The documents to upload (PDF, JPEG, PNG or TIFF)")
                 .takes_value(true)
-                .required(true)))
+                .multiple(true)
+                .required(true))
+            .arg(Arg::with_name("merge")
+                .long("merge")
+                .short("m")
+                .help("Merge all documents into a single document"))
+            .arg(Arg::with_name("interleave")
+                .long("interleave")
+                .short("i")
+                .help("Take pages alternately from each document while merging (useful for duplex scans)")
+                .requires("merge"))
+            .arg(Arg::with_name("reverse")
+                .long("reverse")
+                .short("r")
+                .help("Take the pages of the last document in reverse order while merging (useful for back-sides scanned in reverse order)")
+                .requires("merge")))
         .subcommand(SubCommand::with_name("inbox")
             .about("Manage your inbox")
             .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -80,7 +110,11 @@ async fn main() {
             .subcommand(SubCommand::with_name("show")
                 .about("Show a document in your inbox")
                 .arg(Arg::with_name("id")
-                    .help("Document ID or Index to show (default shows oldest document)")))
+                    .help("Document ID or Index to show (default shows oldest document)"))
+                .arg(Arg::with_name("certainty")
+                    .long("certainty")
+                    .help("Minimal score of suggested labels between 0 and 1 (default is configured by the server)")
+                    .takes_value(true)))
             .subcommand(SubCommand::with_name("get")
                 .about("Downloads a fragment from an inboxed document")
                 .arg(Arg::with_name("id")
@@ -113,9 +147,52 @@ async fn main() {
                     .takes_value(true)
                     .multiple(true))
                 .arg(Arg::with_name("properties")
-                    .help("The labels to put on the document")
+                    .long("property")
+                    .short("p")
+                    .help("A property to put on the document (like key=value)")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1))
+                .arg(Arg::with_name("title")
+                    .long("title")
+                    .short("t")
+                    .help("The title of the document")
+                    .takes_value(true))
+                .arg(Arg::with_name("date")
+                    .long("date")
+                    .short("d")
+                    .help("The date of the document (YYYY-MM-DD)")
+                    .takes_value(true)))
+            .subcommand(SubCommand::with_name("split")
+                .about("Split a document from your inbox into multiple documents")
+                .arg(Arg::with_name("id")
+                    .help("Document ID to split")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("ranges")
+                    .help("The page ranges of the resulting documents (like 1-3 or 4)")
+                    .takes_value(true)
+                    .multiple(true)
+                    .required(true)))
+            .subcommand(SubCommand::with_name("merge")
+                .about("Merge multiple documents from your inbox into a single document")
+                .arg(Arg::with_name("ids")
+                    .help("Document IDs to merge in order")
+                    .takes_value(true)
+                    .multiple(true)
+                    .min_values(2)
+                    .required(true)))
+            .subcommand(SubCommand::with_name("edit")
+                .about("Rotate, delete or reorder pages of a document in your inbox")
+                .arg(Arg::with_name("id")
+                    .help("Document ID to edit")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("pages")
+                    .help("The pages to keep in the new order with an optional clockwise rotation (like 1-3, 5:90 or 4)")
                     .takes_value(true)
-                    .multiple(true))))
+                    .multiple(true)
+                    .required(true))))
         .subcommand(SubCommand::with_name("archive")
             .about("Access your document archive")
             .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -147,7 +224,111 @@ async fn main() {
                 .arg(Arg::with_name("query")
                     .help("The search query")
                     .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("from")
+                    .long("from")
+                    .help("Only find documents dated on or after this date (YYYY-MM-DD)")
+                    .takes_value(true))
+                .arg(Arg::with_name("to")
+                    .long("to")
+                    .help("Only find documents dated on or before this date (YYYY-MM-DD)")
+                    .takes_value(true))
+                .arg(Arg::with_name("label")
+                    .long("label")
+                    .short("l")
+                    .help("Only find documents having this label or one of its children (like Finance/Bank)")
+                    .takes_value(true))
+                .arg(Arg::with_name("property")
+                    .long("property")
+                    .short("p")
+                    .help("Only find documents with a value of this property within the range given by --min and --max")
+                    .takes_value(true))
+                .arg(Arg::with_name("min")
+                    .long("min")
+                    .help("The lowest value of the property (inclusive)")
+                    .takes_value(true)
+                    .requires("property"))
+                .arg(Arg::with_name("max")
+                    .long("max")
+                    .help("The highest value of the property (inclusive)")
+                    .takes_value(true)
+                    .requires("property"))))
+        .subcommand(SubCommand::with_name("labels")
+            .about("Manage your labels")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::GlobalVersion)
+            .setting(AppSettings::InferSubcommands)
+            .subcommand(SubCommand::with_name("list")
+                .about("List all labels"))
+            .subcommand(SubCommand::with_name("describe")
+                .about("Set the description and color of a label")
+                .arg(Arg::with_name("label")
+                    .help("The label to describe")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("description")
+                    .long("description")
+                    .short("d")
+                    .help("The description of the label")
+                    .takes_value(true))
+                .arg(Arg::with_name("color")
+                    .long("color")
+                    .help("The color of the label (like #1e90ff)")
+                    .takes_value(true)))
+            .subcommand(SubCommand::with_name("rename")
+                .about("Rename a label on all archived documents")
+                .arg(Arg::with_name("label")
+                    .help("The label to rename")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("to")
+                    .help("The new name of the label")
+                    .takes_value(true)
+                    .required(true)))
+            .subcommand(SubCommand::with_name("merge")
+                .about("Merge a label into another label on all archived documents")
+                .arg(Arg::with_name("label")
+                    .help("The label to merge")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("into")
+                    .help("The label to merge into")
+                    .takes_value(true)
+                    .required(true)))
+            .subcommand(SubCommand::with_name("delete")
+                .about("Remove a label from all archived documents")
+                .arg(Arg::with_name("label")
+                    .help("The label to delete")
+                    .takes_value(true)
+                    .required(true))))
+        .subcommand(SubCommand::with_name("keys")
+            .about("Manage API keys")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::GlobalVersion)
+            .setting(AppSettings::InferSubcommands)
+            .subcommand(SubCommand::with_name("list")
+                .about("List all API keys created at runtime"))
+            .subcommand(SubCommand::with_name("create")
+                .about("Create an API key and show its secret")
+                .arg(Arg::with_name("name")
+                    .help("The name of the key used as username")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("scopes")
+                    .long("scope")
+                    .short("s")
+                    .help("Restrict the key to a scope (upload, read, review or admin)")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)))
+            .subcommand(SubCommand::with_name("revoke")
+                .about("Revoke an API key")
+                .arg(Arg::with_name("name")
+                    .help("The name of the key to revoke")
+                    .takes_value(true)
                     .required(true))))
+        .subcommand(SubCommand::with_name("audit")
+            .about("Show which user performed which action"))
         .get_matches();
 
     match exec(&matches).await {
@@ -176,6 +357,9 @@ async fn exec(matches: &clap::ArgMatches<'_>) -> Result<Box<dyn Output>> {
                 ("upload", Some(matches)) => upload::exec(matches, &mut client).await,
                 ("inbox", Some(matches)) => inbox::exec(matches, &mut client).await,
                 ("archive", Some(matches)) => archive::exec(matches, &mut client).await,
+                ("labels", Some(matches)) => labels::exec(matches, &mut client).await,
+                ("keys", Some(matches)) => keys::exec(matches, &mut client).await,
+                ("audit", Some(matches)) => audit::exec(matches, &mut client).await,
 
                 _ => unreachable!()
             }
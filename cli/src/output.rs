@@ -48,6 +48,7 @@ impl SimpleOutput for DocInfo {
 
         writeln!(w, "    {}: {}", "Title".bold(), self.metadata.title.as_ref().map(|title| title.to_string()).unwrap_or_else(String::new))?;
         writeln!(w, "    {}: {}", "Pages".bold(), self.metadata.pages)?;
+        writeln!(w, "    {}: {}", "Date".bold(), self.metadata.date.map(|date| date.to_string()).unwrap_or_else(String::new))?;
 
         writeln!(w, "    {}:", "Labels".bold())?;
         for label in self.metadata.labels.iter() {
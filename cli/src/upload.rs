@@ -1,7 +1,7 @@
 use std::io::Write;
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::Colorize;
 
 use crate::client::Client;
@@ -9,12 +9,46 @@ use crate::output::{Output, SimpleOutput};
 use crate::proto::api::upload::UploadResponse;
 
 pub async fn exec(matches: &clap::ArgMatches<'_>, client: &mut Client) -> Result<Box<dyn Output>> {
-    let pdf = Path::new(matches.value_of_os("pdf").expect("Document missing"));
-    let pdf = tokio::fs::File::open(pdf).await?;
+    let paths = matches.values_of_os("documents").expect("Documents missing")
+        .map(Path::new)
+        .collect::<Vec<_>>();
 
-    let response = client.upload(pdf).await?;
+    if matches.is_present("merge") {
+        let mut documents = Vec::new();
+        for path in paths {
+            let content_type = content_type(path)?;
+            documents.push((tokio::fs::File::open(path).await?, content_type));
+        }
 
-    return Ok(Box::new(response));
+        let response = client.upload_merged(documents, matches.is_present("interleave"), matches.is_present("reverse")).await?;
+
+        return Ok(Box::new(response));
+    }
+
+    let mut responses = Vec::new();
+    for path in paths {
+        let content_type = content_type(path)?;
+
+        let document = tokio::fs::File::open(path).await?;
+
+        responses.push(client.upload(document, content_type).await?);
+    }
+
+    return Ok(Box::new(responses));
+}
+
+/// Guesses the content type of a document from its file extension.
+fn content_type(path: &Path) -> Result<&'static str> {
+    let extension = path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    return match extension.as_deref() {
+        Some("pdf") => Ok("application/pdf"),
+        Some("jpg") | Some("jpeg") => Ok("image/jpeg"),
+        Some("png") => Ok("image/png"),
+        Some("tif") | Some("tiff") => Ok("image/tiff"),
+        _ => Err(anyhow!("Unsupported document type: {}", path.display())),
+    };
 }
 
 impl SimpleOutput for UploadResponse {
@@ -25,4 +59,14 @@ impl SimpleOutput for UploadResponse {
 
         return Ok(());
     }
-}
\ No newline at end of file
+}
+
+impl SimpleOutput for Vec<UploadResponse> {
+    fn to_text(&self, w: &mut dyn Write) -> Result<()> {
+        for response in self {
+            SimpleOutput::to_text(response, w)?;
+        }
+
+        return Ok(());
+    }
+}
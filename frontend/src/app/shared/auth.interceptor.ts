@@ -1,8 +1,8 @@
 import {Injectable} from '@angular/core';
-import {HttpEvent, HttpHandler, HttpHeaderResponse, HttpInterceptor, HttpRequest} from '@angular/common/http';
-import {Observable, throwError} from 'rxjs';
+import {HttpEvent, HttpHandler, HttpInterceptor, HttpRequest} from '@angular/common/http';
+import {Observable, of, throwError} from 'rxjs';
 import {AuthService} from './services/auth.service';
-import {catchError, tap} from 'rxjs/operators';
+import {catchError, switchMap} from 'rxjs/operators';
 
 @Injectable()
 export class AuthInterceptor implements HttpInterceptor {
@@ -11,17 +11,26 @@ export class AuthInterceptor implements HttpInterceptor {
   }
 
   intercept(request: HttpRequest<unknown>, next: HttpHandler): Observable<HttpEvent<unknown>> {
-    const token = this.authService.token;
-    if (token != null) {
-      request = request.clone({ setHeaders: {Authorization: `Bearer ${token}`}});
+    // Requests managing the session itself are never retried
+    if (request.url.startsWith('/api/auth/')) {
+      return next.handle(this.authorize(request, this.authService.token));
     }
 
-    return next.handle(request)
-      .pipe(tap(event => {
-          if (event instanceof HttpHeaderResponse) {
-            const token = event.headers.get('Authorization');
-            this.authService.update(token);
+    // Refresh the access token shortly before it expires
+    const token$ = this.authService.expiring && this.authService.refreshable
+      ? this.authService.refresh()
+      : of(this.authService.token);
+
+    return token$
+      .pipe(switchMap(token => next.handle(this.authorize(request, token))),
+        catchError(err => {
+          // Retry once with a refreshed access token
+          if (err.status === 401 && this.authService.refreshable) {
+            return this.authService.refresh()
+              .pipe(switchMap(token => next.handle(this.authorize(request, token))));
           }
+
+          return throwError(err);
         }),
         catchError(err => {
           if (err.status === 401) {
@@ -29,8 +38,16 @@ export class AuthInterceptor implements HttpInterceptor {
             // location.reload();
           }
 
-          const error = err.error.message || err.statusText;
+          const error = (err.error && err.error.message) || err.statusText;
           return throwError(error);
         }));
   }
+
+  private authorize(request: HttpRequest<unknown>, token: string | null): HttpRequest<unknown> {
+    if (token == null) {
+      return request;
+    }
+
+    return request.clone({ setHeaders: {Authorization: `Bearer ${token}`}});
+  }
 }
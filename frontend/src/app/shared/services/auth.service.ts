@@ -1,44 +1,77 @@
 import {Injectable} from '@angular/core';
 import {HttpClient} from '@angular/common/http';
-import {BehaviorSubject, Observable} from 'rxjs';
-import {first, map} from 'rxjs/operators';
+import {BehaviorSubject, Observable, throwError} from 'rxjs';
+import {catchError, finalize, first, map, shareReplay} from 'rxjs/operators';
 
-export class Auth {
-  username: string;
-  token: string;
+export class Session {
+  access: string;
+  refresh: string;
 }
 
+/** Seconds before the expiry of the access token to refresh it. */
+const REFRESH_MARGIN = 60;
+
 @Injectable()
 export class AuthService {
   private token$: BehaviorSubject<string | null>;
   private username$: BehaviorSubject<string | null>;
 
+  private refreshing$: Observable<string> | null = null;
+
   constructor(private http: HttpClient) {
     this.token$ = new BehaviorSubject(localStorage.getItem('token'));
     this.username$ = new BehaviorSubject(localStorage.getItem('username'));
   }
 
   public login(username: string, password: string) {
-    this.http.post<Auth>(`/api/auth`, {username, password})
+    this.http.post<Session>(`/api/auth/login`, {username, password})
       .pipe(first())
-      .subscribe(auth => {
+      .subscribe(session => {
         // Store auth state in local storage to keep user logged in between page refreshes
-        localStorage.setItem('token', auth.token);
-        localStorage.setItem('username', auth.username);
-        this.token$.next(auth.token);
-        this.username$.next(auth.username);
-        return auth;
+        this.update(session);
+        localStorage.setItem('username', username);
+        this.username$.next(username);
+        return session;
       });
   }
 
-  public update(token: string) {
-    this.token$.next(token);
-    localStorage.setItem('token', token);
+  /**
+   * Exchanges the refresh token for new tokens and returns the new access token.
+   *
+   * Concurrent requests share a single refresh as each refresh token can be used only once. The user is logged out if
+   * the session can not be continued.
+   */
+  public refresh(): Observable<string> {
+    if (this.refreshing$ === null) {
+      this.refreshing$ = this.http.post<Session>(`/api/auth/refresh`, {refresh: localStorage.getItem('refresh')})
+        .pipe(map(session => {
+            this.update(session);
+            return session.access;
+          }),
+          catchError(err => {
+            if (err.status === 401) {
+              this.logout();
+            }
+
+            return throwError(err);
+          }),
+          finalize(() => this.refreshing$ = null),
+          shareReplay(1));
+    }
+
+    return this.refreshing$;
+  }
+
+  public update(session: Session) {
+    localStorage.setItem('token', session.access);
+    localStorage.setItem('refresh', session.refresh);
+    this.token$.next(session.access);
   }
 
   public logout() {
     // Remove auth state from local storage to log out
     localStorage.removeItem('token');
+    localStorage.removeItem('refresh');
     localStorage.removeItem('username');
     this.token$.next(null);
     this.username$.next(null);
@@ -52,8 +85,26 @@ export class AuthService {
     return this.username$.value;
   }
 
+  public get refreshable(): boolean {
+    return localStorage.getItem('refresh') !== null;
+  }
+
+  /** Whether the access token is about to expire and should be refreshed before using it. */
+  public get expiring(): boolean {
+    const token = this.token$.value;
+    if (token === null) {
+      return false;
+    }
+
+    try {
+      const claims = JSON.parse(atob(token.split('.')[1].replace(/-/g, '+').replace(/_/g, '/')));
+      return claims.exp - REFRESH_MARGIN < Date.now() / 1000;
+    } catch {
+      return true;
+    }
+  }
+
   public get authenticated(): boolean {
-    // TODO: Check if token is expired
     return this.token$.value !== null;
   }
 }
@@ -0,0 +1,73 @@
+#!/usr/bin/env bash
+
+set -xe
+
+# Assembles 'original.pdf' from multiple parts as instructed by 'assemble.json'
+
+# Convert all parts to PDF
+SOURCES=()
+RANGES=()
+INTERMEDIATES=()
+while IFS=$'\t' read -r SOURCE RANGE ROTATE; do
+  if [[ "${SOURCE}" != *.pdf ]]; then
+    "$(dirname "$0")/image2pdf.sh" "${SOURCE}" "${SOURCE}.pdf"
+    SOURCE="${SOURCE}.pdf"
+    INTERMEDIATES+=("${SOURCE}")
+  fi
+
+  if [[ "${ROTATE}" -ne 0 ]]; then
+    # Extract the selected pages of the part and rotate them
+    PART="part-${#SOURCES[@]}.pdf"
+    qpdf --empty --rotate="+${ROTATE}" --pages "${SOURCE}" "${RANGE}" -- "${PART}"
+    SOURCE="${PART}"
+    RANGE="1-z"
+    INTERMEDIATES+=("${PART}")
+  fi
+
+  SOURCES+=("${SOURCE}")
+  RANGES+=("${RANGE}")
+done < <(jq -r '.parts[] | [.source, .pages // "1-z", .rotate // 0] | @tsv' 'assemble.json')
+
+# Collect the pages from all parts
+PAGES=()
+if [[ "$(jq -r '.interleave' 'assemble.json')" == "true" ]]; then
+  # Take the pages alternately from each part
+  COUNTS=()
+  MAX=0
+  for INDEX in "${!SOURCES[@]}"; do
+    SOURCE="${SOURCES[${INDEX}]}"
+
+    if [[ "${RANGES[${INDEX}]}" != "1-z" ]]; then
+      # Extract the selected pages of the part in the selected order
+      PART="pages-${INDEX}.pdf"
+      qpdf --empty --pages "${SOURCE}" "${RANGES[${INDEX}]}" -- "${PART}"
+      SOURCE="${PART}"
+      SOURCES[${INDEX}]="${PART}"
+      INTERMEDIATES+=("${PART}")
+    fi
+
+    COUNT="$(qpdf --show-npages "${SOURCE}")"
+    COUNTS+=("${COUNT}")
+    if [[ "${COUNT}" -gt "${MAX}" ]]; then
+      MAX="${COUNT}"
+    fi
+  done
+
+  for PAGE in $(seq 1 "${MAX}"); do
+    for INDEX in "${!SOURCES[@]}"; do
+      if [[ "${PAGE}" -le "${COUNTS[${INDEX}]}" ]]; then
+        PAGES+=("${SOURCES[${INDEX}]}" "${PAGE}")
+      fi
+    done
+  done
+else
+  # Take the selected pages of each part in order
+  for INDEX in "${!SOURCES[@]}"; do
+    PAGES+=("${SOURCES[${INDEX}]}" "${RANGES[${INDEX}]}")
+  done
+fi
+
+qpdf --empty --pages "${PAGES[@]}" -- 'original.pdf'
+
+# Remove intermediate files
+rm -f "${INTERMEDIATES[@]}"
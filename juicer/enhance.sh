@@ -2,16 +2,22 @@
 
 set -xe
 
-# OCR the original pdf
+# Rotate the pages automatically unless disabled by the second argument
+OPTIONS=()
+if [[ "${2:-true}" == "true" ]]; then
+  OPTIONS+=(--rotate-pages)
+fi
+
+# OCR the input pdf
 ocrmypdf \
   -l eng+deu \
-  --rotate-pages \
+  "${OPTIONS[@]}" \
   --deskew \
   --remove-background \
   --clean \
   --output-type pdfa \
   --pdfa-image-compression jpeg \
-  'original.pdf' \
+  "$1" \
   'document.pdf'
 
 # Extract the text of the final pdf file
@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+
+set -xe
+
+# Converts the image given as first argument to the PDF given as second argument
+IMAGE="$1"
+OUTPUT="$2"
+
+# img2pdf refuses images having an alpha channel, so transparent areas are flattened onto white first
+# JPEG images can not be transparent and are embedded as they are to avoid compressing them again
+FLATTENED=""
+if [[ "${IMAGE}" != *.jpg && "${IMAGE}" != *.jpeg ]]; then
+  FLATTENED="flattened-$(basename "${IMAGE}")"
+  convert "${IMAGE}" -background white -alpha remove -alpha off "${FLATTENED}"
+  IMAGE="${FLATTENED}"
+fi
+
+img2pdf "${IMAGE}" -o "${OUTPUT}"
+
+# Remove intermediate files
+rm -f "${FLATTENED}"
@@ -2,29 +2,64 @@
 
 set -xe
 
+# Assemble the original document from multiple parts if requested
+ROTATE_PAGES="true"
+if [[ -r "assemble.json" ]]; then
+    "$(dirname "$0")/assemble.sh"
+
+    # Pages oriented by the reviewer must not be rotated again
+    if [[ "$(jq -r '.oriented // false' 'assemble.json')" == "true" ]]; then
+        ROTATE_PAGES="false"
+    fi
+fi
+
+# Find the original document and convert it to PDF if required
+if [[ -r "original.pdf" ]]; then
+    INPUT="original.pdf"
+else
+    for ORIGINAL in "original.jpg" "original.png" "original.tiff"; do
+        if [[ -r "${ORIGINAL}" ]]; then
+            echo "Converting ${ORIGINAL} to PDF" >&2
+            "$(dirname "$0")/image2pdf.sh" "${ORIGINAL}" "input.pdf"
+            INPUT="input.pdf"
+            break
+        fi
+    done
+fi
+
 # Sanity checks
-if [[ ! -r "original.pdf" ]]; then
-    echo "Missing original.pdf" >&2
+if [[ -z "${INPUT:-}" ]]; then
+    echo "Missing original document" >&2
     exit 1
 fi
 
-# Extract text from original PDF
-pdftotext "original.pdf" "original.txt"
+# Extract text from input PDF
+pdftotext "${INPUT}" "input.txt"
 
 # Decide whether to enhance or not
-if [[ "$(wc -c < "original.txt")" -lt 10 ]]; then
+if [[ "$(wc -c < "input.txt")" -lt 10 ]]; then
   echo "Document contains no text - enhancing" >&2
-  "$(dirname "$0")/enhance.sh"
+  "$(dirname "$0")/enhance.sh" "${INPUT}" "${ROTATE_PAGES}"
 else
-  # Just copy original PDF and already extracted text
+  # Just copy input PDF and already extracted text
   echo "Document already contains text" >&2
-  cp 'original.pdf' 'document.pdf'
-  cp 'original.txt' 'document.txt'
+  cp "${INPUT}" 'document.pdf'
+  cp 'input.txt' 'document.txt'
 fi
 
-# Extract preview
+# Remove intermediate files
+rm -f 'input.pdf' 'input.txt'
+
+# Extract previews and thumbnails for each page
+# The first page is additionally rendered as 'preview.png' for compatibility
 pdftoppm 'document.pdf' 'preview' -png -f 1 -singlefile
 
+PAGES="$(pdfinfo 'document.pdf' | awk '/^Pages:/ { print $2 }')"
+for PAGE in $(seq 1 "${PAGES}"); do
+  pdftoppm 'document.pdf' "preview-${PAGE}" -png -f "${PAGE}" -l "${PAGE}" -singlefile
+  pdftoppm 'document.pdf' "thumbnail-${PAGE}" -png -f "${PAGE}" -l "${PAGE}" -singlefile -scale-to 256
+done
+
 # Extract additional metadata
 # This splits the pdfinfo output by line on first colon (':'), trims the values, filters for empty values and converts to JSON object
 INFO="$(pdfinfo 'document.pdf' | jq --slurp --raw-input '
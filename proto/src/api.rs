@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::model::*;
@@ -9,7 +10,29 @@ pub mod auth {
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct AuthRequest {
+        /// The account to log in - can be omitted if only a single account exists
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub username: Option<String>,
+
         pub password: String,
+
+        /// Restricts the session to the given scopes - the session gets all scopes of the user if missing
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub scopes: Option<HashSet<Scope>>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct AuthResponse {
+        /// The bearer token used to authenticate requests
+        pub access: String,
+
+        /// The token used to get a new access token before the current one expires
+        pub refresh: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct RefreshRequest {
+        pub refresh: String,
     }
 }
 
@@ -36,14 +59,51 @@ pub mod inbox {
     pub struct GetResponse {
         #[serde(flatten)]
         pub doc: DocInfo,
-        pub suggestions: HashSet<Label>,
+        pub suggestions: Vec<Suggestion>,
+        pub date_suggestions: Vec<NaiveDate>,
+        pub property_suggestions: HashMap<String, Vec<String>>,
+        pub title_suggestion: Option<String>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct ArchiveRequest {
+        #[serde(default)]
+        pub title: Option<String>,
+        #[serde(default)]
+        pub date: Option<NaiveDate>,
         pub labels: HashSet<Label>,
         pub properties: HashMap<String, String>,
     }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct SplitRequest {
+        pub ranges: Vec<PageRange>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct SplitResponse {
+        pub docs: Vec<DocInfo>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct MergeRequest {
+        pub ids: Vec<DocId>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct MergeResponse {
+        pub doc: DocInfo,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct EditRequest {
+        pub pages: Vec<PageEdit>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct EditResponse {
+        pub doc: DocInfo,
+    }
 }
 
 pub mod archive {
@@ -60,4 +120,138 @@ pub mod archive {
         pub count: u64,
         pub docs: Vec<DocInfo>,
     }
-}
\ No newline at end of file
+}
+
+pub mod labels {
+    use super::*;
+
+    /// Quality of the suggestions for a label.
+    ///
+    /// Precision and recall are determined by cross-validation over the archive and are missing if the label has
+    /// never been suggested or assigned respectively.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct LabelStats {
+        pub precision: Option<f64>,
+        pub recall: Option<f64>,
+
+        /// Number of archived documents having the label
+        pub support: u64,
+
+        /// Number of archived documents where the suggested label has been kept by the reviewer
+        pub accepted: u64,
+
+        /// Number of archived documents where the suggested label has been removed by the reviewer
+        pub rejected: u64,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct StatsResponse {
+        pub documents: usize,
+        pub labels: HashMap<Label, LabelStats>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct LabelResponse {
+        pub label: Label,
+
+        /// Number of archived documents having the label, not counting its children
+        pub count: u64,
+
+        /// Number of archived documents having the label or any of its children
+        pub total: u64,
+
+        #[serde(flatten)]
+        pub info: LabelInfo,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub children: Vec<LabelResponse>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ListResponse {
+        /// The top-level labels with their children
+        pub labels: Vec<LabelResponse>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct RenameRequest {
+        pub to: Label,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct MergeRequest {
+        pub into: Label,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ChangeResponse {
+        /// Number of archived documents which have been changed
+        pub documents: usize,
+    }
+}
+
+pub mod audit {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "action")]
+    #[serde(rename_all = "lowercase")]
+    pub enum Action {
+        Upload { doc: DocId },
+        Archive { doc: DocId },
+        Delete { doc: DocId },
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Entry {
+        pub time: DateTime<Utc>,
+
+        /// The user or API key performing the action
+        pub user: String,
+
+        #[serde(flatten)]
+        pub action: Action,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ListResponse {
+        /// All recorded actions, oldest first
+        pub entries: Vec<Entry>,
+    }
+}
+
+pub mod keys {
+    use super::*;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CreateRequest {
+        pub name: String,
+
+        /// Restricts the key to the given scopes - the key is unrestricted if missing
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub scopes: Option<HashSet<Scope>>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct CreateResponse {
+        pub name: String,
+
+        /// The secret of the key - it is not possible to retrieve it later
+        pub secret: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct KeyInfo {
+        pub name: String,
+        pub created: DateTime<Utc>,
+        pub last_used: Option<DateTime<Utc>>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub scopes: Option<HashSet<Scope>>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ListResponse {
+        pub keys: Vec<KeyInfo>,
+    }
+}
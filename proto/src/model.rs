@@ -5,7 +5,7 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Error};
 use base58::{FromBase58, ToBase58};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize, Serializer};
 use uuid::Uuid;
 
@@ -69,11 +69,17 @@ pub enum Kind {
     Preview,
     Plaintext,
     Metadata,
+    PagePreview { page: u32 },
+    Thumbnail { page: u32 },
     Other { name: OsString },
 }
 
 impl Kind {
     pub fn other(name: impl Into<OsString>) -> Self { Self::Other { name: name.into() } }
+
+    pub fn page_preview(page: u32) -> Self { Self::PagePreview { page } }
+
+    pub fn thumbnail(page: u32) -> Self { Self::Thumbnail { page } }
 }
 
 impl<S: Into<String>> From<S> for Kind {
@@ -89,9 +95,106 @@ impl<S: Into<String>> From<S> for Kind {
     }
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PageRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl PageRange {
+    pub fn new(first: u32, last: u32) -> Self { Self { first, last } }
+
+    pub fn single(page: u32) -> Self { Self::new(page, page) }
+}
+
+impl FromStr for PageRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, last) = match s.find('-') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, s),
+        };
+
+        let first = first.trim().parse().map_err(|_| anyhow!("Invalid page range: {}", s))?;
+        let last = last.trim().parse().map_err(|_| anyhow!("Invalid page range: {}", s))?;
+
+        return Ok(Self::new(first, last));
+    }
+}
+
+impl std::fmt::Display for PageRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.first, self.last)
+    }
+}
+
+/// A page of an edited document taken from the current document and rotated clockwise by the given degrees.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PageEdit {
+    pub page: u32,
+
+    #[serde(default)]
+    pub rotate: u16,
+}
+
+/// A label put on documents.
+///
+/// Labels form a hierarchy by separating the levels with a slash (like `Finance/Bank/Statements`).
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize)]
 pub struct Label(String);
 
+impl Label {
+    pub const SEPARATOR: char = '/';
+
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    /// The levels of the label starting with the top-most one.
+    pub fn segments(&self) -> impl Iterator<Item=&str> {
+        return self.0.split(Self::SEPARATOR);
+    }
+
+    /// The name of the label without its parents.
+    pub fn name(&self) -> &str {
+        return self.segments().last().unwrap_or("");
+    }
+
+    pub fn parent(&self) -> Option<Label> {
+        return self.0.rfind(Self::SEPARATOR)
+            .map(|i| Label(self.0[..i].to_string()));
+    }
+
+    /// The label itself and all its parents.
+    pub fn ancestors(&self) -> Vec<Label> {
+        let mut ancestors = vec![self.clone()];
+        while let Some(parent) = ancestors.last().and_then(Label::parent) {
+            ancestors.push(parent);
+        }
+
+        return ancestors;
+    }
+
+    /// Checks if the label is a (transitive) child of the other label.
+    pub fn is_descendant_of(&self, other: &Label) -> bool {
+        return self.0.len() > other.0.len()
+            && self.0.starts_with(&other.0)
+            && self.0[other.0.len()..].starts_with(Self::SEPARATOR);
+    }
+
+    /// Moves the label to another parent if it is the given label or one of its descendants.
+    pub fn rebase(&self, from: &Label, to: &Label) -> Option<Label> {
+        if self == from {
+            return Some(to.clone());
+        }
+
+        if self.is_descendant_of(from) {
+            return Some(Label(format!("{}{}", to.0, &self.0[from.0.len()..])));
+        }
+
+        return None;
+    }
+}
+
 impl std::fmt::Display for Label {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.0)
@@ -110,6 +213,30 @@ impl Borrow<str> for Label {
     fn borrow(&self) -> &str { &self.0 }
 }
 
+/// Details describing a label to the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LabelInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The color used to display the label as hex RGB value (like `#1e90ff`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl LabelInfo {
+    pub fn is_empty(&self) -> bool {
+        return self.description.is_none() && self.color.is_none();
+    }
+}
+
+/// A label proposed for a document with the score of the proposal between `0.0` and `1.0`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub label: Label,
+    pub score: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Metadata {
     pub uploaded: DateTime<Utc>,
@@ -118,6 +245,9 @@ pub struct Metadata {
     pub title: Option<String>,
     pub pages: u32,
 
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+
     pub labels: HashSet<Label>,
 
     pub properties: HashMap<String, String>,
@@ -138,4 +268,53 @@ impl<D, M> From<(D, M)> for DocInfo
             metadata: metadata.into(),
         };
     }
-}
\ No newline at end of file
+}
+
+/// A permission granted to a session or an API key.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Upload documents to the inbox
+    Upload,
+
+    /// Read documents and labels
+    Read,
+
+    /// Review documents in the inbox and archive them
+    Review,
+
+    /// Manage labels, API keys and inspect the audit log
+    Admin,
+}
+
+impl Scope {
+    /// All scopes granted to an unrestricted session or key.
+    pub fn all() -> HashSet<Self> {
+        return vec![Self::Upload, Self::Read, Self::Review, Self::Admin].into_iter().collect();
+    }
+}
+
+impl FromStr for Scope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "upload" => Ok(Self::Upload),
+            "read" => Ok(Self::Read),
+            "review" => Ok(Self::Review),
+            "admin" => Ok(Self::Admin),
+            _ => Err(anyhow!("Invalid scope: {}", s)),
+        };
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Upload => "upload",
+            Self::Read => "read",
+            Self::Review => "review",
+            Self::Admin => "admin",
+        })
+    }
+}